@@ -0,0 +1,70 @@
+use crate::db::{add_holidays, delete_holiday, get_holidays, DbPool};
+use crate::errors::AppError;
+use crate::models::Holiday;
+use crate::services::parse_ics_holidays;
+use chrono::NaiveDate;
+
+#[tauri::command]
+pub async fn list_holidays(db: tauri::State<'_, DbPool>) -> Result<Vec<Holiday>, AppError> {
+    let db_clone = db.0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        get_holidays(&conn)
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
+
+#[tauri::command]
+pub async fn save_holidays(
+    db: tauri::State<'_, DbPool>,
+    holidays: Vec<Holiday>,
+) -> Result<(), AppError> {
+    let db_clone = db.0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        add_holidays(&conn, &holidays)
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
+
+#[tauri::command]
+pub async fn remove_holiday(db: tauri::State<'_, DbPool>, date: NaiveDate) -> Result<(), AppError> {
+    let db_clone = db.0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        delete_holiday(&conn, date)
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
+
+/// Imports all-day events from a local .ics file as holidays. Returns the number imported.
+#[tauri::command]
+pub async fn import_holidays_ics(
+    db: tauri::State<'_, DbPool>,
+    path: String,
+) -> Result<usize, AppError> {
+    let db_clone = db.0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let content = std::fs::read_to_string(&path).map_err(|e| {
+            AppError::Config(format!("Failed to read calendar file {}: {}", path, e))
+        })?;
+        let holidays = parse_ics_holidays(&content)?;
+
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        add_holidays(&conn, &holidays)?;
+        Ok(holidays.len())
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
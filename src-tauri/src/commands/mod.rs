@@ -1,7 +1,11 @@
+pub mod calendar;
+pub mod scheduler;
 pub mod settings;
 pub mod sync;
 pub mod tickets;
 
+pub use calendar::*;
+pub use scheduler::*;
 pub use settings::*;
 pub use sync::*;
 pub use tickets::*;
@@ -0,0 +1,173 @@
+use super::sync::{build_sync_request, AppHandleSink};
+use crate::db::{get_holidays, DbPool};
+use crate::errors::AppError;
+use crate::models::SyncTrigger;
+use crate::services::scheduler::{
+    SchedulerSettings, SchedulerStatus, SyncPlan, SyncSchedule, SyncScheduler,
+};
+use crate::services::sync_engine::SyncEngine;
+use crate::services::WorkCalendar;
+use std::sync::Arc;
+use tauri::{AppHandle, Manager};
+use tauri_plugin_store::StoreExt;
+
+#[tauri::command]
+pub async fn start_scheduler(
+    scheduler: tauri::State<'_, SyncScheduler>,
+    engine: tauri::State<'_, Arc<SyncEngine>>,
+    app_handle: AppHandle,
+    interval_minutes: Option<u64>,
+    schedule: Option<SyncSchedule>,
+) -> Result<SchedulerStatus, AppError> {
+    if interval_minutes == Some(0) {
+        return Err(AppError::Config(
+            "Sync interval must be at least 1 minute".to_string(),
+        ));
+    }
+
+    let mut settings = load_scheduler_settings(app_handle.clone()).await?;
+    settings.enabled = true;
+    if let Some(interval_minutes) = interval_minutes {
+        settings.interval_minutes = interval_minutes;
+        settings.schedule = None;
+    }
+    if schedule.is_some() {
+        settings.schedule = schedule;
+    }
+
+    // Validate before persisting so a bad cron expression doesn't break the next launch
+    let business_hours = super::settings::load_business_hours_settings(app_handle.clone()).await?;
+    let calendar = load_work_calendar(&app_handle).await?;
+    let plan = SyncPlan::new(settings.sync_schedule(), &business_hours, calendar)?;
+    save_scheduler_settings(app_handle.clone(), &settings).await?;
+
+    schedule_background_sync(&scheduler, plan, engine.inner().clone(), app_handle);
+    Ok(scheduler.status())
+}
+
+#[tauri::command]
+pub async fn stop_scheduler(
+    scheduler: tauri::State<'_, SyncScheduler>,
+    app_handle: AppHandle,
+) -> Result<SchedulerStatus, AppError> {
+    let mut settings = load_scheduler_settings(app_handle.clone()).await?;
+    settings.enabled = false;
+    save_scheduler_settings(app_handle, &settings).await?;
+
+    scheduler.stop();
+    Ok(scheduler.status())
+}
+
+#[tauri::command]
+pub async fn get_scheduler_status(
+    scheduler: tauri::State<'_, SyncScheduler>,
+) -> Result<SchedulerStatus, AppError> {
+    Ok(scheduler.status())
+}
+
+/// Starts the background sync saved in settings, called once on launch
+pub async fn restore_scheduler(app_handle: AppHandle) -> Result<(), AppError> {
+    let settings = load_scheduler_settings(app_handle.clone()).await?;
+    if !settings.enabled {
+        return Ok(());
+    }
+
+    let business_hours = super::settings::load_business_hours_settings(app_handle.clone()).await?;
+    let calendar = load_work_calendar(&app_handle).await?;
+    let plan = SyncPlan::new(settings.sync_schedule(), &business_hours, calendar)?;
+
+    let scheduler = app_handle.state::<SyncScheduler>();
+    let engine = app_handle.state::<Arc<SyncEngine>>().inner().clone();
+    schedule_background_sync(&scheduler, plan, engine, app_handle.clone());
+    Ok(())
+}
+
+fn schedule_background_sync(
+    scheduler: &SyncScheduler,
+    plan: SyncPlan,
+    engine: Arc<SyncEngine>,
+    app_handle: AppHandle,
+) {
+    scheduler.start(plan, move || {
+        let engine = engine.clone();
+        let app_handle = app_handle.clone();
+        async move { run_background_sync(&engine, app_handle).await }
+    });
+}
+
+async fn run_background_sync(engine: &SyncEngine, app_handle: AppHandle) {
+    let result = async {
+        let jira = super::settings::load_jira_settings(app_handle.clone())
+            .await?
+            .ok_or_else(|| AppError::Config("Jira is not configured".to_string()))?;
+        // The current rules, so edits apply from the next run on
+        let category_rules = super::settings::load_category_rules(app_handle.clone()).await?;
+        let request = build_sync_request(
+            app_handle.clone(),
+            SyncTrigger::Scheduled,
+            &jira.jira_url,
+            &jira.email,
+            category_rules,
+        )
+        .await?;
+        engine.run(request, Arc::new(AppHandleSink(app_handle))).await
+    }
+    .await;
+
+    match result {
+        Ok(summary) => {
+            log::info!("Background sync completed: {} tickets", summary.synced);
+        }
+        Err(AppError::SyncAlreadyInProgress) => {
+            log::info!("Background sync skipped, a sync is already running");
+        }
+        Err(e) => {
+            log::error!("Background sync failed: {}", e);
+        }
+    }
+}
+
+/// Holidays for the schedule, read when it starts like the business hours
+async fn load_work_calendar(app_handle: &AppHandle) -> Result<WorkCalendar, AppError> {
+    let db_clone = app_handle.state::<DbPool>().0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        Ok(WorkCalendar::new(&get_holidays(&conn)?))
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
+
+async fn load_scheduler_settings(app_handle: AppHandle) -> Result<SchedulerSettings, AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    match store.get("scheduler") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| AppError::Config(format!("Failed to parse settings: {}", e))),
+        None => Ok(SchedulerSettings::default()),
+    }
+}
+
+async fn save_scheduler_settings(
+    app_handle: AppHandle,
+    settings: &SchedulerSettings,
+) -> Result<(), AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    let settings_value = serde_json::to_value(settings)
+        .map_err(|e| AppError::Config(format!("Failed to serialize settings: {}", e)))?;
+
+    store.set("scheduler", settings_value);
+
+    store
+        .save()
+        .map_err(|e| AppError::Config(format!("Failed to save settings: {}", e)))?;
+
+    Ok(())
+}
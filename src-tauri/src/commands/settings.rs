@@ -1,4 +1,8 @@
 use crate::errors::AppError;
+use crate::jira::{JiraClient, DEFAULT_SCOPE_JQL};
+use crate::services::categorizer::CategoryRule;
+use crate::services::sla::{validate_policies, SlaPolicy};
+use crate::services::time_calc::{BusinessHoursSettings, WorkSchedule};
 use keyring::Entry;
 use serde::{Deserialize, Serialize};
 use tauri::AppHandle;
@@ -11,6 +15,31 @@ const KEYRING_USER: &str = "jira-api-token";
 pub struct JiraSettings {
     pub jira_url: String,
     pub email: String,
+    #[serde(default)]
+    pub base_jql: Option<String>, // e.g. "project = SUP AND component = Network"
+    #[serde(default)]
+    pub filter_id: Option<u64>, // saved Jira filter
+}
+
+impl JiraSettings {
+    /// The JQL selecting which tickets to sync, combining the saved filter and base JQL
+    pub fn scope_jql(&self) -> String {
+        let mut clauses = Vec::new();
+        if let Some(filter_id) = self.filter_id {
+            clauses.push(format!("filter = {}", filter_id));
+        }
+        if let Some(base_jql) = self.base_jql.as_deref().map(str::trim) {
+            if !base_jql.is_empty() {
+                clauses.push(format!("({})", base_jql));
+            }
+        }
+
+        if clauses.is_empty() {
+            DEFAULT_SCOPE_JQL.to_string()
+        } else {
+            clauses.join(" AND ")
+        }
+    }
 }
 
 #[tauri::command]
@@ -48,10 +77,10 @@ pub async fn verify_jira_connection(
     email: String,
 ) -> Result<serde_json::Value, AppError> {
     let token = get_jira_token().await?;
-    let client = crate::jira::JiraClient::new(&jira_url, &email, &token)?;
+    let client = JiraClient::new(&jira_url, &email, &token)?;
 
-    // Simple verification: try to fetch 1 ticket
-    let _ = client.fetch_tickets(None).await?;
+    // Any authenticated request will do; this one doesn't touch the user's tickets
+    client.verify_connection().await?;
 
     Ok(serde_json::json!({
         "email": email,
@@ -64,8 +93,22 @@ pub async fn save_jira_settings(
     app_handle: AppHandle,
     jira_url: String,
     email: String,
+    base_jql: Option<String>,
+    filter_id: Option<u64>,
 ) -> Result<(), AppError> {
-    let settings = JiraSettings { jira_url, email };
+    let settings = JiraSettings {
+        jira_url,
+        email,
+        base_jql,
+        filter_id,
+    };
+
+    // Reject scopes Jira can't parse before they break the next sync
+    if settings.base_jql.is_some() || settings.filter_id.is_some() {
+        let token = get_jira_token().await?;
+        let client = JiraClient::new(&settings.jira_url, &settings.email, &token)?;
+        client.validate_jql(&settings.scope_jql()).await?;
+    }
 
     let store = app_handle
         .store("settings.json")
@@ -99,3 +142,122 @@ pub async fn load_jira_settings(app_handle: AppHandle) -> Result<Option<JiraSett
         Ok(None)
     }
 }
+
+#[tauri::command]
+pub async fn save_business_hours_settings(
+    app_handle: AppHandle,
+    work_start_hour: u32,
+    work_end_hour: u32,
+    timezone: Option<String>,
+    schedule: Option<WorkSchedule>,
+    always_on_priorities: Option<Vec<String>>,
+) -> Result<(), AppError> {
+    let settings = BusinessHoursSettings {
+        work_start_hour,
+        work_end_hour,
+        timezone: timezone.filter(|tz| !tz.trim().is_empty()),
+        schedule,
+        always_on_priorities: always_on_priorities.unwrap_or_default(),
+    };
+    settings.validate()?;
+
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    let settings_value = serde_json::to_value(&settings)
+        .map_err(|e| AppError::Config(format!("Failed to serialize settings: {}", e)))?;
+
+    store.set("business_hours", settings_value);
+
+    store
+        .save()
+        .map_err(|e| AppError::Config(format!("Failed to save settings: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_business_hours_settings(
+    app_handle: AppHandle,
+) -> Result<BusinessHoursSettings, AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    match store.get("business_hours") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| AppError::Config(format!("Failed to parse settings: {}", e))),
+        None => Ok(BusinessHoursSettings::default()),
+    }
+}
+
+#[tauri::command]
+pub async fn save_sla_policies(
+    app_handle: AppHandle,
+    policies: Vec<SlaPolicy>,
+) -> Result<(), AppError> {
+    validate_policies(&policies)?;
+
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    let policies_value = serde_json::to_value(&policies)
+        .map_err(|e| AppError::Config(format!("Failed to serialize settings: {}", e)))?;
+
+    store.set("sla_policies", policies_value);
+
+    store
+        .save()
+        .map_err(|e| AppError::Config(format!("Failed to save settings: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_sla_policies(app_handle: AppHandle) -> Result<Vec<SlaPolicy>, AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    match store.get("sla_policies") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| AppError::Config(format!("Failed to parse settings: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
+
+#[tauri::command]
+pub async fn save_category_rules(
+    app_handle: AppHandle,
+    rules: Vec<CategoryRule>,
+) -> Result<(), AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    let rules_value = serde_json::to_value(&rules)
+        .map_err(|e| AppError::Config(format!("Failed to serialize settings: {}", e)))?;
+
+    store.set("category_rules", rules_value);
+
+    store
+        .save()
+        .map_err(|e| AppError::Config(format!("Failed to save settings: {}", e)))?;
+
+    Ok(())
+}
+
+#[tauri::command]
+pub async fn load_category_rules(app_handle: AppHandle) -> Result<Vec<CategoryRule>, AppError> {
+    let store = app_handle
+        .store("settings.json")
+        .map_err(|e| AppError::Config(format!("Failed to access store: {}", e)))?;
+
+    match store.get("category_rules") {
+        Some(value) => serde_json::from_value(value.clone())
+            .map_err(|e| AppError::Config(format!("Failed to parse settings: {}", e))),
+        None => Ok(Vec::new()),
+    }
+}
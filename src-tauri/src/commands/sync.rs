@@ -1,197 +1,177 @@
-use crate::db::{get_sync_metadata, set_sync_metadata, upsert_ticket, DbPool};
+use crate::db::{get_sync_metadata, get_sync_runs, DbPool};
 use crate::errors::AppError;
-use crate::jira::JiraClient;
-use crate::services::categorize_ticket;
-use serde::{Deserialize, Serialize};
+use crate::jira::{JiraClient, DEFAULT_SCOPE_JQL};
+use crate::models::{SyncRun, SyncTrigger};
+use crate::services::categorizer::CategoryRule;
+use crate::services::sync_engine::{
+    SyncEngine, SyncProgress, SyncProgressSink, SyncRequest, SyncSummary,
+};
+use serde::Deserialize;
+use std::sync::Arc;
 use tauri::Emitter;
 
-pub struct SyncLock(pub tokio::sync::Mutex<bool>);
-
 #[derive(Deserialize)]
 pub struct CategoryRulesWrapper {
     #[serde(rename = "categoryRules")]
-    pub category_rules: Vec<crate::services::categorizer::CategoryRule>,
+    pub category_rules: Vec<CategoryRule>,
+}
+
+/// Parses the category rules sent along with a manual sync
+fn parse_category_rules(category_rules_json: &str) -> Result<Vec<CategoryRule>, AppError> {
+    let rules_wrapper: CategoryRulesWrapper = serde_json::from_str(category_rules_json)
+        .map_err(|e| AppError::Config(format!("Failed to parse category rules: {}", e)))?;
+    Ok(rules_wrapper.category_rules)
+}
+
+/// Forwards sync lifecycle and progress to the frontend as events
+pub struct AppHandleSink(pub tauri::AppHandle);
+
+impl SyncProgressSink for AppHandleSink {
+    fn started(&self) {
+        self.0.emit("sync-started", ()).ok();
+    }
+
+    fn progress(&self, progress: SyncProgress) {
+        self.0.emit("sync-progress", progress).ok();
+    }
+
+    fn finished(&self, result: &Result<SyncSummary, AppError>) {
+        match result {
+            Ok(summary) => {
+                self.0.emit("sync-complete", summary.clone()).ok();
+            }
+            Err(AppError::SyncCancelled) => {
+                self.0.emit("sync-cancelled", ()).ok();
+            }
+            Err(e) => {
+                self.0.emit("sync-error", e.to_string()).ok();
+            }
+        }
+    }
 }
 
-#[derive(Serialize, Clone)]
-struct SyncProgress {
-    phase: String,
-    current: usize,
-    total: Option<usize>,
+/// Loads the token and scope for a sync
+pub async fn build_sync_request(
+    app_handle: tauri::AppHandle,
+    trigger: SyncTrigger,
+    jira_url: &str,
+    email: &str,
+    category_rules: Vec<CategoryRule>,
+) -> Result<SyncRequest, AppError> {
+    // Get token
+    let token = super::settings::get_jira_token().await?;
+
+    // Create Jira client
+    let client = JiraClient::new(jira_url, email, &token)?;
+
+    let scope_jql = super::settings::load_jira_settings(app_handle)
+        .await?
+        .map(|settings| settings.scope_jql())
+        .unwrap_or_else(|| DEFAULT_SCOPE_JQL.to_string());
+
+    Ok(SyncRequest {
+        trigger,
+        client,
+        scope_jql,
+        category_rules,
+    })
 }
 
 #[tauri::command]
 pub async fn trigger_sync(
-    lock: tauri::State<'_, SyncLock>,
-    db: tauri::State<'_, DbPool>,
+    engine: tauri::State<'_, Arc<SyncEngine>>,
     jira_url: String,
     email: String,
     category_rules_json: String,
     app_handle: tauri::AppHandle,
-) -> Result<serde_json::Value, AppError> {
-    let mut is_syncing = lock.0.lock().await;
-    if *is_syncing {
+) -> Result<SyncSummary, AppError> {
+    if engine.is_syncing() {
         return Err(AppError::SyncAlreadyInProgress);
     }
-    *is_syncing = true;
-    drop(is_syncing);
-
-    // Emit sync started
-    app_handle.emit("sync-started", ()).ok();
-
-    let result = perform_sync(db, jira_url, email, category_rules_json, app_handle.clone()).await;
-
-    let mut is_syncing = lock.0.lock().await;
-    *is_syncing = false;
-
-    // Emit sync completed or error
-    match &result {
-        Ok(data) => {
-            app_handle.emit("sync-complete", data.clone()).ok();
-        }
-        Err(e) => {
-            app_handle.emit("sync-error", e.to_string()).ok();
-        }
-    }
 
-    result
+    let request = build_sync_request(
+        app_handle.clone(),
+        SyncTrigger::Manual,
+        &jira_url,
+        &email,
+        parse_category_rules(&category_rules_json)?,
+    )
+    .await?;
+    engine.run(request, Arc::new(AppHandleSink(app_handle))).await
 }
 
-async fn perform_sync(
-    db: tauri::State<'_, DbPool>,
+/// Discards the watermark and re-fetches the whole scope, replacing the stored tickets in one
+/// step while keeping local data such as categories. The summary reports what changed.
+#[tauri::command]
+pub async fn full_resync(
+    engine: tauri::State<'_, Arc<SyncEngine>>,
     jira_url: String,
     email: String,
     category_rules_json: String,
     app_handle: tauri::AppHandle,
-) -> Result<serde_json::Value, AppError> {
-    // Get token
-    let token = super::settings::get_jira_token().await?;
+) -> Result<SyncSummary, AppError> {
+    if engine.is_syncing() {
+        return Err(AppError::SyncAlreadyInProgress);
+    }
 
-    // Parse category rules
-    let rules_wrapper: CategoryRulesWrapper =
-        serde_json::from_str(&category_rules_json).map_err(|e| {
-            AppError::Config(format!("Failed to parse category rules: {}", e))
-        })?;
-    let category_rules = rules_wrapper.category_rules;
+    let request = build_sync_request(
+        app_handle.clone(),
+        SyncTrigger::FullResync,
+        &jira_url,
+        &email,
+        parse_category_rules(&category_rules_json)?,
+    )
+    .await?;
+    engine.run(request, Arc::new(AppHandleSink(app_handle))).await
+}
 
-    // Create Jira client
-    let client = JiraClient::new(&jira_url, &email, &token)?;
+/// Asks the running sync to stop. Returns false when no sync is running.
+#[tauri::command]
+pub async fn cancel_sync(engine: tauri::State<'_, Arc<SyncEngine>>) -> Result<bool, AppError> {
+    Ok(engine.cancel())
+}
 
-    // Get last sync timestamp
+#[tauri::command]
+pub async fn get_sync_status(
+    db: tauri::State<'_, DbPool>,
+    engine: tauri::State<'_, Arc<SyncEngine>>,
+) -> Result<serde_json::Value, AppError> {
     let db_clone = db.0.clone();
-    let last_sync_ts = tauri::async_runtime::spawn_blocking(move || {
+    let (last_sync_at, last_run) = tauri::async_runtime::spawn_blocking(move || {
         let conn = db_clone
             .lock()
             .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-        get_sync_metadata(&conn, "last_sync_at")
+        let last_sync_at = get_sync_metadata(&conn, "last_sync_at")?;
+        let last_run = get_sync_runs(&conn, 1)?.into_iter().next();
+        Ok::<_, AppError>((last_sync_at, last_run))
     })
     .await
     .map_err(|_| AppError::Internal("Task join failed".to_string()))??;
 
-    // Emit fetching phase
-    app_handle
-        .emit(
-            "sync-progress",
-            SyncProgress {
-                phase: "fetching".to_string(),
-                current: 0,
-                total: None,
-            },
-        )
-        .ok();
-
-    // Fetch tickets from Jira
-    let mut tickets = client.fetch_tickets(last_sync_ts.as_deref()).await?;
-
-    let total_count = tickets.len();
-
-    // Emit categorizing phase
-    app_handle
-        .emit(
-            "sync-progress",
-            SyncProgress {
-                phase: "categorizing".to_string(),
-                current: 0,
-                total: Some(total_count),
-            },
-        )
-        .ok();
-
-    // Categorize tickets
-    for (idx, ticket) in tickets.iter_mut().enumerate() {
-        ticket.category = categorize_ticket(ticket, &category_rules);
-
-        // Emit progress every 10 tickets
-        if idx % 10 == 0 {
-            app_handle
-                .emit(
-                    "sync-progress",
-                    SyncProgress {
-                        phase: "categorizing".to_string(),
-                        current: idx,
-                        total: Some(total_count),
-                    },
-                )
-                .ok();
-        }
-    }
-
-    let synced_count = tickets.len();
-
-    // Emit saving phase
-    app_handle
-        .emit(
-            "sync-progress",
-            SyncProgress {
-                phase: "saving".to_string(),
-                current: 0,
-                total: Some(synced_count),
-            },
-        )
-        .ok();
-
-    // Store in database
-    let db_clone = db.0.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        let conn = db_clone
-            .lock()
-            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-
-        for ticket in &tickets {
-            upsert_ticket(&conn, ticket)?;
-        }
-
-        // Update last sync timestamp
-        let now = chrono::Utc::now().to_rfc3339();
-        set_sync_metadata(&conn, "last_sync_at", &now)?;
-
-        Ok::<(), AppError>(())
-    })
-    .await
-    .map_err(|_| AppError::Internal("Task join failed".to_string()))??;
+    let last_error = last_run.as_ref().and_then(|run| run.error.clone());
 
     Ok(serde_json::json!({
-        "synced": synced_count,
-        "errors": 0,
-        "last_sync": chrono::Utc::now().to_rfc3339()
+        "is_syncing": engine.is_syncing(),
+        "last_sync_at": last_sync_at,
+        "last_error": last_error,
+        "last_run": last_run
     }))
 }
 
+/// The last `limit` sync runs, newest first
 #[tauri::command]
-pub async fn get_sync_status(db: tauri::State<'_, DbPool>) -> Result<serde_json::Value, AppError> {
+pub async fn get_sync_history(
+    db: tauri::State<'_, DbPool>,
+    limit: Option<u32>,
+) -> Result<Vec<SyncRun>, AppError> {
+    let limit = limit.unwrap_or(20);
     let db_clone = db.0.clone();
-    let last_sync_at = tauri::async_runtime::spawn_blocking(move || {
+    tauri::async_runtime::spawn_blocking(move || {
         let conn = db_clone
             .lock()
             .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-        get_sync_metadata(&conn, "last_sync_at")
+        get_sync_runs(&conn, limit)
     })
     .await
-    .map_err(|_| AppError::Internal("Task join failed".to_string()))??;
-
-    Ok(serde_json::json!({
-        "is_syncing": false,
-        "last_sync_at": last_sync_at,
-        "last_error": null
-    }))
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
 }
@@ -1,31 +1,90 @@
-use crate::db::{get_aggregations, get_tickets, DbPool};
+use crate::db::{get_aggregations, get_ticket_slas, get_tickets_matching, DbPool};
 use crate::errors::AppError;
-use crate::models::{AggregationResult, Ticket};
+use crate::models::{AggregationResult, DashboardQuery, Ticket, TicketFilter, TicketSla};
+use crate::services::time_calc::parse_jira_timestamp;
+use chrono::{DateTime, FixedOffset, NaiveDate, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
 
+/// `query` picks the date range and period size, by default the last 12 months, and `filter`
+/// the tickets. With `as_of` (a timestamp, or a date meaning the end of that day in the team's
+/// timezone), the dashboard shows the tickets as they were at that moment.
 #[tauri::command]
 pub async fn get_dashboard_data(
     db: tauri::State<'_, DbPool>,
+    app_handle: tauri::AppHandle,
+    query: Option<DashboardQuery>,
+    filter: Option<TicketFilter>,
+    as_of: Option<String>,
 ) -> Result<AggregationResult, AppError> {
+    let query = query.unwrap_or_default();
+    let filter = filter.unwrap_or_default();
+    let business_hours = super::settings::load_business_hours_settings(app_handle.clone()).await?;
+    let sla_policies = super::settings::load_sla_policies(app_handle).await?;
+    let as_of = as_of
+        .map(|as_of| parse_as_of(&as_of, business_hours.team_timezone()?))
+        .transpose()?;
+
+    let db_clone = db.0.clone();
+    tauri::async_runtime::spawn_blocking(move || {
+        let conn = db_clone
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        get_aggregations(&conn, &business_hours, &sla_policies, &query, &filter, as_of)
+    })
+    .await
+    .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+}
+
+#[tauri::command]
+pub async fn get_all_tickets(
+    db: tauri::State<'_, DbPool>,
+    filter: Option<TicketFilter>,
+) -> Result<Vec<Ticket>, AppError> {
+    let filter = filter.unwrap_or_default();
     let db_clone = db.0.clone();
     tauri::async_runtime::spawn_blocking(move || {
         let conn = db_clone
             .lock()
             .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-        get_aggregations(&conn)
+        get_tickets_matching(&conn, &filter)
     })
     .await
     .map_err(|_| AppError::Internal("Task join failed".to_string()))?
 }
 
 #[tauri::command]
-pub async fn get_all_tickets(db: tauri::State<'_, DbPool>) -> Result<Vec<Ticket>, AppError> {
+pub async fn get_sla_status(
+    db: tauri::State<'_, DbPool>,
+    app_handle: tauri::AppHandle,
+) -> Result<Vec<TicketSla>, AppError> {
+    let business_hours = super::settings::load_business_hours_settings(app_handle.clone()).await?;
+    let sla_policies = super::settings::load_sla_policies(app_handle).await?;
+
     let db_clone = db.0.clone();
     tauri::async_runtime::spawn_blocking(move || {
         let conn = db_clone
             .lock()
             .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-        get_tickets(&conn)
+        get_ticket_slas(&conn, &business_hours, &sla_policies)
     })
     .await
     .map_err(|_| AppError::Internal("Task join failed".to_string()))?
 }
+
+fn parse_as_of(value: &str, team_tz: Option<Tz>) -> Result<DateTime<FixedOffset>, AppError> {
+    if let Some(timestamp) = parse_jira_timestamp(value) {
+        return Ok(timestamp);
+    }
+
+    let date = NaiveDate::parse_from_str(value, "%Y-%m-%d")
+        .map_err(|_| AppError::Config(format!("Invalid as_of date: {}", value)))?;
+    let end_of_day = date.and_time(NaiveTime::from_hms_opt(23, 59, 59).expect("valid time"));
+    let as_of = match team_tz {
+        Some(tz) => tz
+            .from_local_datetime(&end_of_day)
+            .latest()
+            .map(|at| at.fixed_offset()),
+        None => Some(Utc.from_utc_datetime(&end_of_day).fixed_offset()),
+    };
+    as_of.ok_or_else(|| AppError::Config(format!("Invalid as_of date: {}", value)))
+}
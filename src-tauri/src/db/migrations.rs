@@ -1,37 +1,20 @@
 use crate::errors::{AppError, DbError};
 use rusqlite::Connection;
+use std::path::{Path, PathBuf};
 
-const SCHEMA_VERSION: i32 = 1;
-
-pub fn initialize_database(conn: &Connection) -> Result<(), AppError> {
-    let current_version = get_schema_version(conn)?;
-
-    if current_version == 0 {
-        create_schema_v1(conn)?;
-        set_schema_version(conn, SCHEMA_VERSION)?;
-    } else if current_version < SCHEMA_VERSION {
-        migrate_schema(conn, current_version)?;
-    }
-
-    Ok(())
-}
-
-fn get_schema_version(conn: &Connection) -> Result<i32, AppError> {
-    let version: i32 = conn
-        .pragma_query_value(None, "user_version", |row| row.get(0))
-        .map_err(DbError::from)?;
-    Ok(version)
+/// One schema change. Applying it brings the database to `version`.
+pub struct Migration {
+    pub version: i32,
+    pub description: &'static str,
+    pub sql: &'static str,
 }
 
-fn set_schema_version(conn: &Connection, version: i32) -> Result<(), AppError> {
-    conn.pragma_update(None, "user_version", version)
-        .map_err(DbError::from)?;
-    Ok(())
-}
-
-fn create_schema_v1(conn: &Connection) -> Result<(), AppError> {
-    conn.execute_batch(
-        r#"
+/// Every schema change in order. Append new steps here; never edit a released one.
+pub const MIGRATIONS: &[Migration] = &[
+    Migration {
+        version: 1,
+        description: "tickets and sync metadata",
+        sql: r#"
         CREATE TABLE IF NOT EXISTS tickets (
             id INTEGER PRIMARY KEY AUTOINCREMENT,
             jira_key TEXT NOT NULL UNIQUE,
@@ -60,13 +43,356 @@ fn create_schema_v1(conn: &Connection) -> Result<(), AppError> {
             value TEXT NOT NULL
         );
         "#,
-    )
-    .map_err(|e| DbError::Migration(format!("Failed to create schema v1: {}", e)))?;
+    },
+    Migration {
+        version: 2,
+        description: "status transitions",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS status_transitions (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            jira_key TEXT NOT NULL,
+            from_status TEXT,
+            to_status TEXT NOT NULL,
+            author TEXT,
+            transitioned_at TEXT NOT NULL
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_transitions_jira_key ON status_transitions(jira_key);
+        CREATE INDEX IF NOT EXISTS idx_transitions_at ON status_transitions(transitioned_at);
+        "#,
+    },
+    // Tickets that left the sync scope are tombstoned rather than deleted so they can come back
+    Migration {
+        version: 3,
+        description: "out-of-scope tombstones",
+        sql: r#"
+        ALTER TABLE tickets ADD COLUMN out_of_scope_at TEXT;
+
+        CREATE VIEW IF NOT EXISTS active_tickets AS
+            SELECT * FROM tickets WHERE out_of_scope_at IS NULL;
+        "#,
+    },
+    Migration {
+        version: 4,
+        description: "holidays",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS holidays (
+            date TEXT PRIMARY KEY,
+            name TEXT
+        );
+        "#,
+    },
+    Migration {
+        version: 5,
+        description: "sync run history",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS sync_runs (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            trigger TEXT NOT NULL,
+            status TEXT NOT NULL,
+            started_at TEXT NOT NULL,
+            finished_at TEXT,
+            duration_ms INTEGER,
+            fetched INTEGER NOT NULL DEFAULT 0,
+            inserted INTEGER NOT NULL DEFAULT 0,
+            updated INTEGER NOT NULL DEFAULT 0,
+            unchanged INTEGER NOT NULL DEFAULT 0,
+            out_of_scope INTEGER NOT NULL DEFAULT 0,
+            error TEXT
+        );
 
+        CREATE INDEX IF NOT EXISTS idx_sync_runs_started ON sync_runs(started_at);
+        "#,
+    },
+    Migration {
+        version: 6,
+        description: "sync checkpoints",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS sync_checkpoints (
+            scope_jql TEXT PRIMARY KEY,
+            jql TEXT NOT NULL,
+            next_page_token TEXT,
+            pages INTEGER NOT NULL DEFAULT 0,
+            tickets INTEGER NOT NULL DEFAULT 0,
+            max_updated TEXT,
+            started_at TEXT NOT NULL
+        );
+        "#,
+    },
+    // A full resync fetches into these and swaps them in once the whole scope has arrived
+    Migration {
+        version: 7,
+        description: "full resync staging",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS tickets_staging (
+            jira_key TEXT PRIMARY KEY,
+            summary TEXT NOT NULL,
+            status TEXT NOT NULL,
+            priority TEXT NOT NULL,
+            issue_type TEXT NOT NULL,
+            assignee TEXT,
+            reporter TEXT,
+            created_at TEXT NOT NULL,
+            updated_at TEXT NOT NULL,
+            resolved_at TEXT,
+            labels TEXT NOT NULL DEFAULT '',
+            project_key TEXT NOT NULL,
+            category TEXT
+        );
+
+        CREATE TABLE IF NOT EXISTS status_transitions_staging (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            jira_key TEXT NOT NULL,
+            from_status TEXT,
+            to_status TEXT NOT NULL,
+            author TEXT,
+            transitioned_at TEXT NOT NULL
+        );
+        "#,
+    },
+    // Tracked fields of a ticket from `valid_from` (its `updated_at`) on, seeded with the
+    // current state so existing tickets have a starting point
+    Migration {
+        version: 8,
+        description: "ticket snapshots",
+        sql: r#"
+        CREATE TABLE IF NOT EXISTS ticket_snapshots (
+            id INTEGER PRIMARY KEY AUTOINCREMENT,
+            jira_key TEXT NOT NULL,
+            valid_from TEXT NOT NULL,
+            summary TEXT NOT NULL,
+            status TEXT NOT NULL,
+            priority TEXT NOT NULL,
+            issue_type TEXT NOT NULL,
+            assignee TEXT,
+            resolved_at TEXT,
+            labels TEXT NOT NULL DEFAULT '',
+            project_key TEXT NOT NULL,
+            category TEXT
+        );
+
+        CREATE INDEX IF NOT EXISTS idx_snapshots_jira_key ON ticket_snapshots(jira_key, id);
+
+        INSERT INTO ticket_snapshots (
+            jira_key, valid_from, summary, status, priority, issue_type, assignee,
+            resolved_at, labels, project_key, category
+        )
+        SELECT
+            jira_key, updated_at, summary, status, priority, issue_type, assignee,
+            resolved_at, labels, project_key, category
+        FROM tickets ORDER BY id;
+        "#,
+    },
+];
+
+/// Brings the schema up to the latest version
+pub fn initialize_database(conn: &Connection) -> Result<(), AppError> {
+    apply_migrations(conn, MIGRATIONS)
+}
+
+/// Like `initialize_database`, but first copies an existing database that needs migrating
+/// next to `db_path`, so a failed or faulty migration can't cost the user their data.
+/// Returns the path of the backup, if one was made.
+pub fn initialize_database_with_backup(
+    conn: &Connection,
+    db_path: &Path,
+) -> Result<Option<PathBuf>, AppError> {
+    let version = get_schema_version(conn)?;
+    let backup = if version > 0 && version < schema_version(MIGRATIONS) {
+        let backup_path = backup_path(db_path, version);
+        backup_database(conn, &backup_path)?;
+        log::info!("Backed up schema v{} database to {:?}", version, backup_path);
+        Some(backup_path)
+    } else {
+        None
+    };
+
+    initialize_database(conn)?;
+    Ok(backup)
+}
+
+fn schema_version(migrations: &[Migration]) -> i32 {
+    migrations.last().map_or(0, |migration| migration.version)
+}
+
+/// Applies the steps newer than the database, each in its own transaction together with the
+/// `user_version` bump, so a failing step leaves the database at the previous version
+fn apply_migrations(conn: &Connection, migrations: &[Migration]) -> Result<(), AppError> {
+    let current_version = get_schema_version(conn)?;
+    let head = schema_version(migrations);
+    if current_version > head {
+        return Err(DbError::Migration(format!(
+            "Database schema v{} is newer than this app supports (v{})",
+            current_version, head
+        ))
+        .into());
+    }
+
+    for migration in migrations.iter().filter(|m| m.version > current_version) {
+        let tx = conn.unchecked_transaction().map_err(DbError::from)?;
+        tx.execute_batch(migration.sql).map_err(|e| {
+            DbError::Migration(format!(
+                "Failed to migrate to schema v{} ({}): {}",
+                migration.version, migration.description, e
+            ))
+        })?;
+        set_schema_version(&tx, migration.version)?;
+        tx.commit().map_err(DbError::from)?;
+    }
+
+    Ok(())
+}
+
+fn get_schema_version(conn: &Connection) -> Result<i32, AppError> {
+    let version: i32 = conn
+        .pragma_query_value(None, "user_version", |row| row.get(0))
+        .map_err(DbError::from)?;
+    Ok(version)
+}
+
+fn set_schema_version(conn: &Connection, version: i32) -> Result<(), AppError> {
+    conn.pragma_update(None, "user_version", version)
+        .map_err(DbError::from)?;
     Ok(())
 }
 
-fn migrate_schema(_conn: &Connection, _from_version: i32) -> Result<(), AppError> {
-    // Future migrations will go here
+/// e.g. `tickets.db` at v3 is backed up to `tickets.db.v3.bak`
+fn backup_path(db_path: &Path, version: i32) -> PathBuf {
+    let mut file_name = db_path.file_name().unwrap_or_default().to_os_string();
+    file_name.push(format!(".v{}.bak", version));
+    db_path.with_file_name(file_name)
+}
+
+fn backup_database(conn: &Connection, backup_path: &Path) -> Result<(), AppError> {
+    let path = backup_path
+        .to_str()
+        .ok_or_else(|| DbError::Migration(format!("Invalid backup path: {:?}", backup_path)))?;
+
+    // VACUUM INTO refuses to overwrite, and a backup left by an earlier attempt is stale
+    if backup_path.exists() {
+        std::fs::remove_file(backup_path).map_err(|e| {
+            DbError::Migration(format!("Failed to replace backup {:?}: {}", backup_path, e))
+        })?;
+    }
+
+    conn.execute("VACUUM INTO ?1", [path])
+        .map_err(|e| DbError::Migration(format!("Failed to back up database: {}", e)))?;
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{get_status_transitions_by_key, get_sync_metadata, get_tickets};
+
+    /// A database as the first release created it
+    fn v1_fixture(conn: &Connection) {
+        conn.execute_batch(MIGRATIONS[0].sql).unwrap();
+        set_schema_version(conn, 1).unwrap();
+        conn.execute_batch(
+            r#"
+            INSERT INTO tickets (
+                jira_key, summary, status, priority, issue_type, assignee, reporter,
+                created_at, updated_at, resolved_at, labels, project_key, category
+            ) VALUES
+                ('SUP-1', 'Printer jammed', 'Done', 'High', 'Bug', 'alice', 'bob',
+                 '2025-01-06T08:00:00.000+0000', '2025-01-07T08:00:00.000+0000',
+                 '2025-01-07T08:00:00.000+0000', 'hardware', 'SUP', 'Printing'),
+                ('SUP-2', 'VPN down', 'Open', 'Medium', 'Task', NULL, 'carol',
+                 '2025-01-08T08:00:00.000+0000', '2025-01-08T08:00:00.000+0000',
+                 NULL, '', 'SUP', NULL);
+            INSERT INTO sync_metadata (key, value)
+                VALUES ('last_sync_at', '2025-01-08T09:00:00+00:00');
+            "#,
+        )
+        .unwrap();
+    }
+
+    fn count_rows(conn: &Connection, sql: &str) -> i64 {
+        conn.query_row(sql, [], |row| row.get(0)).unwrap()
+    }
+
+    #[test]
+    fn test_upgrade_v1_fixture_to_head() {
+        let conn = Connection::open_in_memory().unwrap();
+        v1_fixture(&conn);
+
+        initialize_database(&conn).unwrap();
+
+        assert_eq!(get_schema_version(&conn).unwrap(), schema_version(MIGRATIONS));
+        let tickets = get_tickets(&conn).unwrap();
+        assert_eq!(tickets.len(), 2);
+        let printer = tickets.iter().find(|t| t.jira_key == "SUP-1").unwrap();
+        assert_eq!(printer.summary, "Printer jammed");
+        assert_eq!(printer.assignee.as_deref(), Some("alice"));
+        assert_eq!(printer.category.as_deref(), Some("Printing"));
+        assert!(get_status_transitions_by_key(&conn).unwrap().is_empty());
+        assert_eq!(count_rows(&conn, "SELECT COUNT(*) FROM ticket_snapshots"), 2);
+        assert_eq!(
+            get_sync_metadata(&conn, "last_sync_at").unwrap().as_deref(),
+            Some("2025-01-08T09:00:00+00:00")
+        );
+
+        // Running again at head is a no-op
+        initialize_database(&conn).unwrap();
+        assert_eq!(get_tickets(&conn).unwrap().len(), 2);
+    }
+
+    #[test]
+    fn test_failed_step_keeps_previous_version() {
+        let conn = Connection::open_in_memory().unwrap();
+        v1_fixture(&conn);
+        let migrations = [
+            Migration {
+                version: 1,
+                description: "tickets and sync metadata",
+                sql: MIGRATIONS[0].sql,
+            },
+            Migration {
+                version: 2,
+                description: "notes",
+                sql: "CREATE TABLE notes (id INTEGER PRIMARY KEY);",
+            },
+            Migration {
+                version: 3,
+                description: "broken",
+                sql: "CREATE TABLE broken (id INTEGER); SELECT * FROM missing_table;",
+            },
+        ];
+
+        assert!(apply_migrations(&conn, &migrations).is_err());
+
+        assert_eq!(get_schema_version(&conn).unwrap(), 2);
+        let tables = count_rows(
+            &conn,
+            "SELECT COUNT(*) FROM sqlite_master WHERE name IN ('notes', 'broken')",
+        );
+        assert_eq!(tables, 1);
+
+        // A database newer than the app is refused rather than silently used
+        assert!(apply_migrations(&conn, &migrations[..1]).is_err());
+    }
+
+    #[test]
+    fn test_backup_before_migrating() {
+        let dir = std::env::temp_dir().join(format!("ticketdash-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let db_path = dir.join("tickets.db");
+
+        let conn = Connection::open(&db_path).unwrap();
+        v1_fixture(&conn);
+        let backup = initialize_database_with_backup(&conn, &db_path).unwrap();
+        assert_eq!(backup, Some(dir.join("tickets.db.v1.bak")));
+
+        let backup_conn = Connection::open(backup.unwrap()).unwrap();
+        assert_eq!(get_schema_version(&backup_conn).unwrap(), 1);
+        assert_eq!(count_rows(&backup_conn, "SELECT COUNT(*) FROM tickets"), 2);
+
+        // Nothing to back up once at head
+        assert_eq!(initialize_database_with_backup(&conn, &db_path).unwrap(), None);
+
+        drop(backup_conn);
+        drop(conn);
+        std::fs::remove_dir_all(&dir).ok();
+    }
+}
@@ -6,6 +6,7 @@ pub use queries::*;
 
 use crate::errors::{AppError, DbError};
 use rusqlite::Connection;
+use std::path::Path;
 use std::sync::{Arc, Mutex};
 
 pub struct DbPool(pub Arc<Mutex<Connection>>);
@@ -13,7 +14,7 @@ pub struct DbPool(pub Arc<Mutex<Connection>>);
 impl DbPool {
     pub fn new(db_path: &str) -> Result<Self, AppError> {
         let conn = Connection::open(db_path).map_err(DbError::from)?;
-        initialize_database(&conn)?;
+        initialize_database_with_backup(&conn, Path::new(db_path))?;
         Ok(DbPool(Arc::new(Mutex::new(conn))))
     }
 }
@@ -1,14 +1,359 @@
 use crate::errors::{AppError, DbError};
-use crate::models::{AggregationResult, AvgEntry, CountEntry, SummaryStats, Ticket, TimeSeriesEntry};
-use rusqlite::{Connection, params, OptionalExtension};
+use crate::models::{
+    AggregationResult, AvgEntry, CountEntry, CumulativeFlowEntry, DashboardQuery, FlowTimeStats,
+    Holiday, Period, SlaComplianceEntry, StatusDurationEntry, StatusTransition, SummaryStats,
+    SyncCheckpoint, SyncRun, SyncTrigger, Ticket, TicketFilter, TicketSla, TimeSeriesEntry,
+};
+use crate::services::sla::{self, SlaPolicy};
+use crate::services::time_calc::{
+    business_hours_between_instants, hours_between, parse_jira_timestamp, BusinessHoursSettings,
+    WorkCalendar,
+};
+use chrono::{DateTime, FixedOffset, NaiveDate, Utc};
+use rusqlite::{Connection, params, params_from_iter, OptionalExtension};
+use std::collections::{BTreeSet, HashMap};
+
+// Status names that bound cycle time
+const IN_PROGRESS_STATUS: &str = "In Progress";
+const DONE_STATUS: &str = "Done";
+
+// Periods in the series when the dashboard query has no start date
+const DEFAULT_PERIODS: u32 = 12;
+// Longest series the dashboard computes, e.g. about three years of days
+const MAX_PERIODS: usize = 1100;
+
+// Appends a snapshot of each ticket whose tracked fields differ from its latest snapshot,
+// limited to the ticket with key ?1 unless it is NULL
+const SNAPSHOT_CHANGED_TICKETS: &str = r#"
+    INSERT INTO ticket_snapshots (
+        jira_key, valid_from, summary, status, priority, issue_type, assignee,
+        resolved_at, labels, project_key, category
+    )
+    SELECT
+        t.jira_key, t.updated_at, t.summary, t.status, t.priority, t.issue_type, t.assignee,
+        t.resolved_at, t.labels, t.project_key, t.category
+    FROM tickets t
+    LEFT JOIN ticket_snapshots s ON s.id =
+        (SELECT MAX(id) FROM ticket_snapshots WHERE jira_key = t.jira_key)
+    WHERE (?1 IS NULL OR t.jira_key = ?1)
+        AND (s.id IS NULL OR s.summary IS NOT t.summary OR s.status IS NOT t.status
+            OR s.priority IS NOT t.priority OR s.issue_type IS NOT t.issue_type
+            OR s.assignee IS NOT t.assignee OR s.resolved_at IS NOT t.resolved_at
+            OR s.labels IS NOT t.labels OR s.project_key IS NOT t.project_key
+            OR s.category IS NOT t.category)
+"#;
+
+// Where aggregations read tickets from. Both are fixed names, never user input.
+const ACTIVE_TICKETS: &str = "active_tickets";
+const TICKETS_AS_OF: &str = "temp.tickets_as_of";
+
+/// The tickets the ticket-based aggregations cover
+#[derive(Clone, Copy)]
+struct TicketSelection<'a> {
+    source: &'a str,
+    filter: Option<&'a TicketFilter>,
+    created_from: Option<NaiveDate>,
+    created_to: Option<NaiveDate>,
+}
+
+impl<'a> TicketSelection<'a> {
+    fn all(source: &'a str) -> Self {
+        TicketSelection {
+            source,
+            filter: None,
+            created_from: None,
+            created_to: None,
+        }
+    }
+
+    /// SQL condition on rows of `source` and the values of its `?` parameters
+    fn condition(&self) -> (String, Vec<String>) {
+        let mut conditions = Vec::new();
+        let mut params = Vec::new();
+        // The creation date in the offset Jira reported it with, as in `covers`
+        if let Some(from) = self.created_from {
+            conditions.push("substr(created_at, 1, 10) >= ?".to_string());
+            params.push(from.to_string());
+        }
+        if let Some(to) = self.created_to {
+            conditions.push("substr(created_at, 1, 10) <= ?".to_string());
+            params.push(to.to_string());
+        }
+
+        if let Some(filter) = self.filter {
+            // Whether a ticket's field matches one value. Missing assignees and categories
+            // match the names the charts show for them.
+            let fields = [
+                ("project_key = ?", &filter.projects),
+                ("COALESCE(assignee, 'Unassigned') = ?", &filter.assignees),
+                ("issue_type = ?", &filter.issue_types),
+                // Labels are stored comma-separated
+                ("instr(',' || labels || ',', ',' || ? || ',') > 0", &filter.labels),
+                ("COALESCE(category, 'Uncategorized') = ?", &filter.categories),
+                ("status = ?", &filter.statuses),
+            ];
+            for (matches, set) in fields {
+                let any_of = |values: &BTreeSet<String>| vec![matches; values.len()].join(" OR ");
+                if !set.include.is_empty() {
+                    conditions.push(format!("({})", any_of(&set.include)));
+                    params.extend(set.include.iter().cloned());
+                }
+                if !set.exclude.is_empty() {
+                    conditions.push(format!("NOT ({})", any_of(&set.exclude)));
+                    params.extend(set.exclude.iter().cloned());
+                }
+            }
+        }
+
+        if conditions.is_empty() {
+            ("1".to_string(), params)
+        } else {
+            (conditions.join(" AND "), params)
+        }
+    }
+
+    fn covers(&self, created_at: DateTime<FixedOffset>) -> bool {
+        let date = created_at.date_naive();
+        self.created_from.is_none_or(|from| date >= from)
+            && self.created_to.is_none_or(|to| date <= to)
+    }
+}
+
+/// How an upsert changed the stored ticket
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum UpsertOutcome {
+    Inserted,
+    Updated,
+    // Same `updated_at` as the stored copy
+    Unchanged,
+}
+
+/// Upserts a batch of tickets with statements prepared once for the whole batch, returning the
+/// outcome for each ticket in order. Run it in a transaction to write the batch at once.
+pub fn upsert_tickets(
+    conn: &Connection,
+    tickets: &[Ticket],
+) -> Result<Vec<UpsertOutcome>, AppError> {
+    let mut select_stmt = conn
+        .prepare_cached("SELECT updated_at FROM tickets WHERE jira_key = ?1")
+        .map_err(DbError::from)?;
+    let mut upsert_stmt = conn
+        .prepare_cached(
+            r#"
+            INSERT INTO tickets (
+                jira_key, summary, status, priority, issue_type, assignee, reporter,
+                created_at, updated_at, resolved_at, labels, project_key, category
+            ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+            ON CONFLICT(jira_key) DO UPDATE SET
+                summary = excluded.summary,
+                status = excluded.status,
+                priority = excluded.priority,
+                issue_type = excluded.issue_type,
+                assignee = excluded.assignee,
+                reporter = excluded.reporter,
+                updated_at = excluded.updated_at,
+                resolved_at = excluded.resolved_at,
+                labels = excluded.labels,
+                category = excluded.category,
+                out_of_scope_at = NULL
+            "#,
+        )
+        .map_err(DbError::from)?;
+
+    let mut snapshot_stmt = conn.prepare_cached(SNAPSHOT_CHANGED_TICKETS).map_err(DbError::from)?;
+
+    let mut outcomes = Vec::with_capacity(tickets.len());
+    for ticket in tickets {
+        let stored_updated_at: Option<String> = select_stmt
+            .query_row(params![ticket.jira_key], |row| row.get(0))
+            .optional()
+            .map_err(DbError::from)?;
+
+        upsert_stmt
+            .execute(params![
+                ticket.jira_key,
+                ticket.summary,
+                ticket.status,
+                ticket.priority,
+                ticket.issue_type,
+                ticket.assignee,
+                ticket.reporter,
+                ticket.created_at,
+                ticket.updated_at,
+                ticket.resolved_at,
+                ticket.labels,
+                ticket.project_key,
+                ticket.category,
+            ])
+            .map_err(DbError::from)?;
+        snapshot_stmt
+            .execute(params![ticket.jira_key])
+            .map_err(DbError::from)?;
+
+        outcomes.push(match stored_updated_at {
+            None => UpsertOutcome::Inserted,
+            Some(updated_at) if updated_at == ticket.updated_at => UpsertOutcome::Unchanged,
+            Some(_) => UpsertOutcome::Updated,
+        });
+    }
+
+    Ok(outcomes)
+}
+
+/// Tombstones tickets missing from `in_scope_keys` and restores previously tombstoned ones that
+/// are back in scope. Returns the number of newly tombstoned tickets.
+pub fn reconcile_scope(
+    conn: &Connection,
+    in_scope_keys: &[String],
+    now: &str,
+) -> Result<usize, AppError> {
+    conn.execute_batch(
+        "CREATE TEMP TABLE IF NOT EXISTS scope_keys (jira_key TEXT PRIMARY KEY); \
+         DELETE FROM scope_keys;",
+    )
+    .map_err(DbError::from)?;
+
+    {
+        let mut stmt = conn
+            .prepare("INSERT OR IGNORE INTO scope_keys (jira_key) VALUES (?1)")
+            .map_err(DbError::from)?;
+        for key in in_scope_keys {
+            stmt.execute(params![key]).map_err(DbError::from)?;
+        }
+    }
+
+    let tombstoned = conn
+        .execute(
+            "UPDATE tickets SET out_of_scope_at = ?1 \
+             WHERE out_of_scope_at IS NULL AND jira_key NOT IN (SELECT jira_key FROM scope_keys)",
+            params![now],
+        )
+        .map_err(DbError::from)?;
 
-pub fn upsert_ticket(conn: &Connection, ticket: &Ticket) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE tickets SET out_of_scope_at = NULL \
+         WHERE out_of_scope_at IS NOT NULL AND jira_key IN (SELECT jira_key FROM scope_keys)",
+        [],
+    )
+    .map_err(DbError::from)?;
+
+    conn.execute("DELETE FROM scope_keys", [])
+        .map_err(DbError::from)?;
+
+    Ok(tombstoned)
+}
+
+/// What a full resync changed, compared with the tickets stored before it
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ResyncCounts {
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    // Tombstoned because Jira no longer returned them
+    pub removed: usize,
+}
+
+pub fn clear_staged_tickets(conn: &Connection) -> Result<(), AppError> {
+    conn.execute_batch("DELETE FROM tickets_staging; DELETE FROM status_transitions_staging;")
+        .map_err(DbError::from)?;
+    Ok(())
+}
+
+/// Adds fetched tickets and their status histories to the full resync staging tables
+pub fn stage_tickets(
+    conn: &Connection,
+    tickets: &[Ticket],
+    transitions: &[StatusTransition],
+) -> Result<(), AppError> {
+    let mut ticket_stmt = conn
+        .prepare(
+            "INSERT OR REPLACE INTO tickets_staging ( \
+             jira_key, summary, status, priority, issue_type, assignee, reporter, \
+             created_at, updated_at, resolved_at, labels, project_key, category \
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)",
+        )
+        .map_err(DbError::from)?;
+    for ticket in tickets {
+        ticket_stmt
+            .execute(params![
+                ticket.jira_key,
+                ticket.summary,
+                ticket.status,
+                ticket.priority,
+                ticket.issue_type,
+                ticket.assignee,
+                ticket.reporter,
+                ticket.created_at,
+                ticket.updated_at,
+                ticket.resolved_at,
+                ticket.labels,
+                ticket.project_key,
+                ticket.category,
+            ])
+            .map_err(DbError::from)?;
+    }
+
+    let mut transition_stmt = conn
+        .prepare(
+            "INSERT INTO status_transitions_staging \
+             (jira_key, from_status, to_status, author, transitioned_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .map_err(DbError::from)?;
+    for transition in transitions {
+        transition_stmt
+            .execute(params![
+                transition.jira_key,
+                transition.from_status,
+                transition.to_status,
+                transition.author,
+                transition.transitioned_at,
+            ])
+            .map_err(DbError::from)?;
+    }
+
+    Ok(())
+}
+
+/// Replaces the stored tickets with the staged ones: staged tickets are inserted or
+/// overwritten, stored tickets missing from the stage are tombstoned and the status histories
+/// of staged tickets are replaced. Categories are replaced too, so a ticket the rules no
+/// longer match loses its category. Empties the stage; run it in a transaction to make the
+/// swap atomic.
+pub fn swap_staged_tickets(conn: &Connection, now: &str) -> Result<ResyncCounts, AppError> {
+    let count = |sql: &str| -> Result<usize, AppError> {
+        let count: i64 = conn
+            .query_row(sql, [], |row| row.get(0))
+            .map_err(DbError::from)?;
+        Ok(count as usize)
+    };
+
+    let inserted = count(
+        "SELECT COUNT(*) FROM tickets_staging s \
+         WHERE NOT EXISTS (SELECT 1 FROM active_tickets t WHERE t.jira_key = s.jira_key)",
+    )?;
+    let updated = count(
+        "SELECT COUNT(*) FROM tickets_staging s JOIN active_tickets t ON t.jira_key = s.jira_key \
+         WHERE t.summary IS NOT s.summary OR t.status IS NOT s.status \
+            OR t.priority IS NOT s.priority OR t.issue_type IS NOT s.issue_type \
+            OR t.assignee IS NOT s.assignee OR t.reporter IS NOT s.reporter \
+            OR t.created_at IS NOT s.created_at OR t.updated_at IS NOT s.updated_at \
+            OR t.resolved_at IS NOT s.resolved_at OR t.labels IS NOT s.labels \
+            OR t.project_key IS NOT s.project_key OR t.category IS NOT s.category",
+    )?;
+    let staged = count("SELECT COUNT(*) FROM tickets_staging")?;
+
+    // `WHERE true` keeps SQLite from parsing ON CONFLICT as a join constraint
     conn.execute(
         r#"
         INSERT INTO tickets (
             jira_key, summary, status, priority, issue_type, assignee, reporter,
             created_at, updated_at, resolved_at, labels, project_key, category
-        ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13)
+        )
+        SELECT
+            jira_key, summary, status, priority, issue_type, assignee, reporter,
+            created_at, updated_at, resolved_at, labels, project_key, category
+        FROM tickets_staging WHERE true
         ON CONFLICT(jira_key) DO UPDATE SET
             summary = excluded.summary,
             status = excluded.status,
@@ -16,43 +361,149 @@ pub fn upsert_ticket(conn: &Connection, ticket: &Ticket) -> Result<(), AppError>
             issue_type = excluded.issue_type,
             assignee = excluded.assignee,
             reporter = excluded.reporter,
+            created_at = excluded.created_at,
             updated_at = excluded.updated_at,
             resolved_at = excluded.resolved_at,
             labels = excluded.labels,
-            category = excluded.category
+            project_key = excluded.project_key,
+            category = excluded.category,
+            out_of_scope_at = NULL
+        "#,
+        [],
+    )
+    .map_err(DbError::from)?;
+
+    conn.execute(SNAPSHOT_CHANGED_TICKETS, params![None::<String>])
+        .map_err(DbError::from)?;
+
+    let removed = conn
+        .execute(
+            "UPDATE tickets SET out_of_scope_at = ?1 WHERE out_of_scope_at IS NULL \
+             AND jira_key NOT IN (SELECT jira_key FROM tickets_staging)",
+            params![now],
+        )
+        .map_err(DbError::from)?;
+
+    conn.execute_batch(
+        r#"
+        DELETE FROM status_transitions
+            WHERE jira_key IN (SELECT jira_key FROM tickets_staging);
+        INSERT INTO status_transitions (jira_key, from_status, to_status, author, transitioned_at)
+            SELECT jira_key, from_status, to_status, author, transitioned_at
+            FROM status_transitions_staging ORDER BY id;
         "#,
-        params![
-            ticket.jira_key,
-            ticket.summary,
-            ticket.status,
-            ticket.priority,
-            ticket.issue_type,
-            ticket.assignee,
-            ticket.reporter,
-            ticket.created_at,
-            ticket.updated_at,
-            ticket.resolved_at,
-            ticket.labels,
-            ticket.project_key,
-            ticket.category,
-        ],
     )
     .map_err(DbError::from)?;
 
+    clear_staged_tickets(conn)?;
+
+    Ok(ResyncCounts {
+        inserted,
+        updated,
+        unchanged: staged - inserted - updated,
+        removed,
+    })
+}
+
+/// Replaces the stored status history of a ticket with the one fetched from Jira
+pub fn replace_status_transitions(
+    conn: &Connection,
+    jira_key: &str,
+    transitions: &[StatusTransition],
+) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM status_transitions WHERE jira_key = ?1",
+        params![jira_key],
+    )
+    .map_err(DbError::from)?;
+
+    let mut stmt = conn
+        .prepare_cached(
+            "INSERT INTO status_transitions (jira_key, from_status, to_status, author, transitioned_at) \
+             VALUES (?1, ?2, ?3, ?4, ?5)",
+        )
+        .map_err(DbError::from)?;
+
+    for transition in transitions {
+        stmt.execute(params![
+            transition.jira_key,
+            transition.from_status,
+            transition.to_status,
+            transition.author,
+            transition.transitioned_at,
+        ])
+        .map_err(DbError::from)?;
+    }
+
     Ok(())
 }
 
-pub fn get_tickets(conn: &Connection) -> Result<Vec<Ticket>, AppError> {
+pub fn get_status_transitions_by_key(
+    conn: &Connection,
+) -> Result<HashMap<String, Vec<StatusTransition>>, AppError> {
     let mut stmt = conn
         .prepare(
-            "SELECT id, jira_key, summary, status, priority, issue_type, assignee, reporter, \
-             created_at, updated_at, resolved_at, labels, project_key, category \
-             FROM tickets ORDER BY created_at DESC",
+            "SELECT jira_key, from_status, to_status, author, transitioned_at \
+             FROM status_transitions ORDER BY jira_key, id",
         )
         .map_err(DbError::from)?;
 
-    let tickets = stmt
+    let transitions = stmt
         .query_map([], |row| {
+            Ok(StatusTransition {
+                jira_key: row.get(0)?,
+                from_status: row.get(1)?,
+                to_status: row.get(2)?,
+                author: row.get(3)?,
+                transitioned_at: row.get(4)?,
+            })
+        })
+        .map_err(DbError::from)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(DbError::from)?;
+
+    let mut by_key: HashMap<String, Vec<StatusTransition>> = HashMap::new();
+    for transition in transitions {
+        by_key
+            .entry(transition.jira_key.clone())
+            .or_default()
+            .push(transition);
+    }
+    // Timestamps keep the offset Jira reported them with, so they don't sort as text
+    for history in by_key.values_mut() {
+        history.sort_by_cached_key(|t| parse_jira_timestamp(&t.transitioned_at));
+    }
+
+    Ok(by_key)
+}
+
+pub fn get_tickets(conn: &Connection) -> Result<Vec<Ticket>, AppError> {
+    load_tickets(conn, &TicketSelection::all(ACTIVE_TICKETS))
+}
+
+pub fn get_tickets_matching(
+    conn: &Connection,
+    filter: &TicketFilter,
+) -> Result<Vec<Ticket>, AppError> {
+    let selection = TicketSelection {
+        filter: Some(filter),
+        ..TicketSelection::all(ACTIVE_TICKETS)
+    };
+    load_tickets(conn, &selection)
+}
+
+fn load_tickets(conn: &Connection, selection: &TicketSelection) -> Result<Vec<Ticket>, AppError> {
+    let (condition, params) = selection.condition();
+    let query = format!(
+        "SELECT id, jira_key, summary, status, priority, issue_type, assignee, reporter, \
+         created_at, updated_at, resolved_at, labels, project_key, category \
+         FROM {} WHERE {} ORDER BY created_at DESC",
+        selection.source, condition
+    );
+    let mut stmt = conn.prepare(&query).map_err(DbError::from)?;
+
+    let tickets = stmt
+        .query_map(params_from_iter(params), |row| {
             Ok(Ticket {
                 id: row.get(0)?,
                 jira_key: row.get(1)?,
@@ -77,25 +528,322 @@ pub fn get_tickets(conn: &Connection) -> Result<Vec<Ticket>, AppError> {
     Ok(tickets)
 }
 
-pub fn get_aggregations(conn: &Connection) -> Result<AggregationResult, AppError> {
-    let tickets_by_status = get_count_by_field(conn, "status")?;
-    let tickets_by_priority = get_count_by_field(conn, "priority")?;
-    let tickets_by_category = get_count_by_field(conn, "category")?;
-    let tickets_over_time = get_tickets_over_time(conn)?;
-    let resolution_time_by_priority = get_resolution_time_by_priority(conn)?;
-    let summary = get_summary_stats(conn)?;
+/// Every dashboard chart for the tickets matching `filter`, over the range and granularity of
+/// `query`. With `as_of`, the charts describe the tickets as they were at that moment,
+/// reconstructed from snapshots and status history.
+pub fn get_aggregations(
+    conn: &Connection,
+    business_hours: &BusinessHoursSettings,
+    sla_policies: &[SlaPolicy],
+    query: &DashboardQuery,
+    filter: &TicketFilter,
+    as_of: Option<DateTime<FixedOffset>>,
+) -> Result<AggregationResult, AppError> {
+    let now = as_of.unwrap_or_else(|| Utc::now().fixed_offset());
+    let granularity = query.granularity;
+    let to = query.to.unwrap_or(now.date_naive());
+    let from = match query.from {
+        Some(from) => Some(from),
+        None => granularity.sub_periods(granularity.period_start(to), DEFAULT_PERIODS - 1),
+    };
+    let too_large = || AppError::Config("Dashboard range is too large".to_string());
+    let from = from.ok_or_else(too_large)?;
+    if from > to {
+        return Err(AppError::Config(format!(
+            "Dashboard range starts ({}) after it ends ({})",
+            from, to
+        )));
+    }
+    let periods = granularity.periods(from, to, MAX_PERIODS).ok_or_else(too_large)?;
+
+    let source = match as_of {
+        Some(as_of) => {
+            store_tickets_as_of(conn, as_of)?;
+            TICKETS_AS_OF
+        }
+        None => ACTIVE_TICKETS,
+    };
+    let selection = TicketSelection {
+        source,
+        filter: Some(filter),
+        created_from: Some(from),
+        created_to: Some(to),
+    };
+
+    let tickets_by_status = get_count_by_field(conn, &selection, "status")?;
+    let tickets_by_priority = get_count_by_field(conn, &selection, "priority")?;
+    let tickets_by_category = get_count_by_field(conn, &selection, "category")?;
+    let calendar = WorkCalendar::new(&get_holidays(conn)?);
+    let resolution_times = load_resolution_times(conn, &selection, business_hours, &calendar)?;
+    let resolution_time_by_priority = get_resolution_time_by_priority(&resolution_times);
+
+    // The series look at every ticket, e.g. to count older tickets still open in a period
+    let any_creation_date = TicketSelection {
+        created_from: None,
+        created_to: None,
+        ..selection
+    };
+    let mut flow_tickets = load_flow_tickets(conn, &any_creation_date, now)?;
+    let tickets_over_time = get_tickets_over_time(&flow_tickets, &periods);
+    let cumulative_flow = get_cumulative_flow(&flow_tickets, &periods);
+    flow_tickets.retain(|ticket| selection.covers(ticket.created_at));
+
+    let time_in_status = get_time_in_status(&flow_tickets, now);
+    let cycle_time = get_cycle_time(&flow_tickets);
+    let lead_time = get_lead_time(&flow_tickets);
+    let sla_compliance =
+        get_sla_compliance(conn, &selection, business_hours, sla_policies, &calendar, now)?;
+    let summary = get_summary_stats(conn, &selection, &resolution_times)?;
 
     Ok(AggregationResult {
         tickets_by_status,
         tickets_by_priority,
         tickets_by_category,
         tickets_over_time,
+        cumulative_flow,
         resolution_time_by_priority,
+        time_in_status,
+        cycle_time,
+        lead_time,
+        sla_compliance,
         summary,
     })
 }
 
-fn get_count_by_field(conn: &Connection, field: &str) -> Result<Vec<CountEntry>, AppError> {
+/// SLA status of every active ticket that a policy applies to
+pub fn get_ticket_slas(
+    conn: &Connection,
+    business_hours: &BusinessHoursSettings,
+    sla_policies: &[SlaPolicy],
+) -> Result<Vec<TicketSla>, AppError> {
+    let calendar = WorkCalendar::new(&get_holidays(conn)?);
+    sla::evaluate_tickets(
+        &get_tickets(conn)?,
+        &get_status_transitions_by_key(conn)?,
+        sla_policies,
+        business_hours,
+        &calendar,
+        Utc::now().fixed_offset(),
+    )
+}
+
+fn get_sla_compliance(
+    conn: &Connection,
+    selection: &TicketSelection,
+    business_hours: &BusinessHoursSettings,
+    sla_policies: &[SlaPolicy],
+    calendar: &WorkCalendar,
+    now: DateTime<FixedOffset>,
+) -> Result<Vec<SlaComplianceEntry>, AppError> {
+    if sla_policies.is_empty() {
+        return Ok(Vec::new());
+    }
+
+    let results = sla::evaluate_tickets(
+        &load_tickets(conn, selection)?,
+        &transitions_until(get_status_transitions_by_key(conn)?, now),
+        sla_policies,
+        business_hours,
+        calendar,
+        now,
+    )?;
+
+    Ok(sla::compliance_by_policy(&results, sla_policies))
+}
+
+/// Drops the transitions that happened after `at`
+fn transitions_until(
+    mut transitions: HashMap<String, Vec<StatusTransition>>,
+    at: DateTime<FixedOffset>,
+) -> HashMap<String, Vec<StatusTransition>> {
+    for history in transitions.values_mut() {
+        history.retain(|t| parse_jira_timestamp(&t.transitioned_at).is_some_and(|ts| ts <= at));
+    }
+    transitions
+}
+
+/// Tracked fields of a ticket from `valid_from` on
+struct TicketSnapshot {
+    valid_from: Option<DateTime<FixedOffset>>,
+    summary: String,
+    status: String,
+    priority: String,
+    issue_type: String,
+    assignee: Option<String>,
+    resolved_at: Option<String>,
+    labels: String,
+    project_key: String,
+    category: Option<String>,
+}
+
+/// The tickets as they were at `as_of`: tickets created later or already out of scope are left
+/// out, tracked fields come from the snapshot valid then (or the oldest one), and the status
+/// from the status history, which goes back further than the snapshots
+pub fn get_tickets_as_of(
+    conn: &Connection,
+    as_of: DateTime<FixedOffset>,
+) -> Result<Vec<Ticket>, AppError> {
+    let mut snapshots_by_key: HashMap<String, Vec<TicketSnapshot>> = HashMap::new();
+    let mut stmt = conn
+        .prepare(
+            "SELECT jira_key, valid_from, summary, status, priority, issue_type, assignee, \
+             resolved_at, labels, project_key, category FROM ticket_snapshots \
+             ORDER BY jira_key, id",
+        )
+        .map_err(DbError::from)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                TicketSnapshot {
+                    valid_from: parse_jira_timestamp(&row.get::<_, String>(1)?),
+                    summary: row.get(2)?,
+                    status: row.get(3)?,
+                    priority: row.get(4)?,
+                    issue_type: row.get(5)?,
+                    assignee: row.get(6)?,
+                    resolved_at: row.get(7)?,
+                    labels: row.get(8)?,
+                    project_key: row.get(9)?,
+                    category: row.get(10)?,
+                },
+            ))
+        })
+        .map_err(DbError::from)?;
+    for row in rows {
+        let (jira_key, snapshot) = row.map_err(DbError::from)?;
+        snapshots_by_key.entry(jira_key).or_default().push(snapshot);
+    }
+
+    let transitions = get_status_transitions_by_key(conn)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, jira_key, summary, status, priority, issue_type, assignee, reporter, \
+             created_at, updated_at, resolved_at, labels, project_key, category, out_of_scope_at \
+             FROM tickets",
+        )
+        .map_err(DbError::from)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                Ticket {
+                    id: row.get(0)?,
+                    jira_key: row.get(1)?,
+                    summary: row.get(2)?,
+                    status: row.get(3)?,
+                    priority: row.get(4)?,
+                    issue_type: row.get(5)?,
+                    assignee: row.get(6)?,
+                    reporter: row.get(7)?,
+                    created_at: row.get(8)?,
+                    updated_at: row.get(9)?,
+                    resolved_at: row.get(10)?,
+                    labels: row.get(11)?,
+                    project_key: row.get(12)?,
+                    category: row.get(13)?,
+                },
+                row.get::<_, Option<String>>(14)?,
+            ))
+        })
+        .map_err(DbError::from)?;
+
+    let happened_by =
+        |ts: Option<&str>| ts.and_then(parse_jira_timestamp).is_some_and(|ts| ts <= as_of);
+
+    let mut tickets = Vec::new();
+    for row in rows {
+        let (mut ticket, out_of_scope_at) = row.map_err(DbError::from)?;
+        if !happened_by(Some(&ticket.created_at)) || happened_by(out_of_scope_at.as_deref()) {
+            continue;
+        }
+
+        let current_resolved_at = ticket.resolved_at.take();
+        if let Some(snapshots) = snapshots_by_key.remove(&ticket.jira_key) {
+            let valid = snapshots
+                .iter()
+                .rposition(|s| s.valid_from.is_some_and(|from| from <= as_of))
+                .unwrap_or(0);
+            let snapshot = snapshots.into_iter().nth(valid).expect("index in bounds");
+            ticket.summary = snapshot.summary;
+            ticket.status = snapshot.status;
+            ticket.priority = snapshot.priority;
+            ticket.issue_type = snapshot.issue_type;
+            ticket.assignee = snapshot.assignee;
+            ticket.labels = snapshot.labels;
+            ticket.project_key = snapshot.project_key;
+            ticket.category = snapshot.category;
+            ticket.resolved_at = snapshot.resolved_at;
+        }
+        // A resolution is only known once it happened, and snapshots lag behind it
+        if !happened_by(ticket.resolved_at.as_deref()) {
+            ticket.resolved_at = current_resolved_at.filter(|at| happened_by(Some(at)));
+        }
+
+        if let Some(history) = transitions.get(&ticket.jira_key) {
+            let last = history
+                .iter()
+                .rposition(|t| happened_by(Some(&t.transitioned_at)));
+            let status = match last {
+                Some(last) => Some(history[last].to_status.clone()),
+                None => history.first().and_then(|t| t.from_status.clone()),
+            };
+            if let Some(status) = status {
+                ticket.status = status;
+            }
+        }
+
+        tickets.push(ticket);
+    }
+
+    Ok(tickets)
+}
+
+/// Fills the temporary `tickets_as_of` table the aggregations read from when given `as_of`
+fn store_tickets_as_of(conn: &Connection, as_of: DateTime<FixedOffset>) -> Result<(), AppError> {
+    let tickets = get_tickets_as_of(conn, as_of)?;
+
+    conn.execute_batch(
+        "CREATE TEMP TABLE IF NOT EXISTS tickets_as_of AS SELECT * FROM tickets WHERE 0; \
+         DELETE FROM temp.tickets_as_of;",
+    )
+    .map_err(DbError::from)?;
+
+    let mut stmt = conn
+        .prepare(
+            "INSERT INTO temp.tickets_as_of ( \
+             id, jira_key, summary, status, priority, issue_type, assignee, reporter, \
+             created_at, updated_at, resolved_at, labels, project_key, category \
+             ) VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14)",
+        )
+        .map_err(DbError::from)?;
+    for ticket in &tickets {
+        stmt.execute(params![
+            ticket.id,
+            ticket.jira_key,
+            ticket.summary,
+            ticket.status,
+            ticket.priority,
+            ticket.issue_type,
+            ticket.assignee,
+            ticket.reporter,
+            ticket.created_at,
+            ticket.updated_at,
+            ticket.resolved_at,
+            ticket.labels,
+            ticket.project_key,
+            ticket.category,
+        ])
+        .map_err(DbError::from)?;
+    }
+
+    Ok(())
+}
+
+fn get_count_by_field(
+    conn: &Connection,
+    selection: &TicketSelection,
+    field: &str,
+) -> Result<Vec<CountEntry>, AppError> {
     // Whitelist of allowed field names to prevent SQL injection
     let allowed_fields = ["status", "priority", "category"];
     if !allowed_fields.contains(&field) {
@@ -103,14 +851,16 @@ fn get_count_by_field(conn: &Connection, field: &str) -> Result<Vec<CountEntry>,
     }
 
     // Safe to use now that field is validated
+    let (condition, params) = selection.condition();
     let query = format!(
-        "SELECT COALESCE({}, 'Uncategorized') as name, COUNT(*) as count FROM tickets GROUP BY {} ORDER BY count DESC",
-        field, field
+        "SELECT COALESCE({}, 'Uncategorized') as name, COUNT(*) as count FROM {} WHERE {} \
+         GROUP BY {} ORDER BY count DESC",
+        field, selection.source, condition, field
     );
 
     let mut stmt = conn.prepare(&query).map_err(DbError::from)?;
     let entries = stmt
-        .query_map([], |row| {
+        .query_map(params_from_iter(params), |row| {
             Ok(CountEntry {
                 name: row.get(0)?,
                 count: row.get(1)?,
@@ -123,101 +873,171 @@ fn get_count_by_field(conn: &Connection, field: &str) -> Result<Vec<CountEntry>,
     Ok(entries)
 }
 
-fn get_tickets_over_time(conn: &Connection) -> Result<Vec<TimeSeriesEntry>, AppError> {
-    // Group by month and count created/resolved tickets
-    let mut stmt = conn.prepare(
-        r#"
-        SELECT
-            strftime('%Y-%m', created_at) as month,
-            COUNT(*) as created_count,
-            SUM(CASE WHEN resolved_at IS NOT NULL AND strftime('%Y-%m', resolved_at) = strftime('%Y-%m', created_at) THEN 1 ELSE 0 END) as resolved_count
-        FROM tickets
-        WHERE created_at IS NOT NULL
-        GROUP BY month
-        ORDER BY month ASC
-        LIMIT 12
-        "#
-    ).map_err(DbError::from)?;
+/// Tickets created and resolved in each period, and those still open at its end. Resolutions
+/// count in the period they happened in.
+fn get_tickets_over_time(tickets: &[FlowTicket], periods: &[Period]) -> Vec<TimeSeriesEntry> {
+    periods
+        .iter()
+        .map(|period| {
+            let in_period = |at: DateTime<FixedOffset>| {
+                let date = at.date_naive();
+                date >= period.start && date < period.end
+            };
+            let before_end = |at: DateTime<FixedOffset>| at.date_naive() < period.end;
 
-    let entries = stmt
-        .query_map([], |row| {
-            Ok(TimeSeriesEntry {
-                date: row.get(0)?,
-                created: row.get(1)?,
-                resolved: row.get(2)?,
-            })
+            let mut entry = TimeSeriesEntry {
+                date: period.label.clone(),
+                created: 0,
+                resolved: 0,
+                open: 0,
+            };
+            for ticket in tickets {
+                if in_period(ticket.created_at) {
+                    entry.created += 1;
+                }
+                if ticket.resolved_at.is_some_and(in_period) {
+                    entry.resolved += 1;
+                }
+                if before_end(ticket.created_at) && !ticket.resolved_at.is_some_and(before_end) {
+                    entry.open += 1;
+                }
+            }
+            entry
         })
-        .map_err(DbError::from)?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(DbError::from)?;
-
-    Ok(entries)
+        .collect()
 }
 
-fn get_resolution_time_by_priority(conn: &Connection) -> Result<Vec<AvgEntry>, AppError> {
-    // Calculate average resolution time in calendar hours (not business hours yet)
-    // TODO: Implement business hours calculation using time_calc::business_hours_between
+/// Tickets per status at the end of each period, following the status history. Statuses are
+/// listed in the order tickets first reached them, roughly the workflow order.
+fn get_cumulative_flow(tickets: &[FlowTicket], periods: &[Period]) -> Vec<CumulativeFlowEntry> {
+    let mut counts_by_status: HashMap<&str, Vec<u32>> = HashMap::new();
+    let mut first_reached: HashMap<&str, DateTime<FixedOffset>> = HashMap::new();
 
-    // Get list of priorities first
-    let priorities: Vec<String> = conn
-        .prepare("SELECT DISTINCT priority FROM tickets WHERE resolved_at IS NOT NULL ORDER BY priority")
-        .map_err(DbError::from)?
-        .query_map([], |row| row.get(0))
-        .map_err(DbError::from)?
-        .collect::<Result<Vec<_>, _>>()
-        .map_err(DbError::from)?;
+    for ticket in tickets {
+        // When the ticket entered each status, in order
+        let mut history = vec![(ticket.created_at, ticket.initial_status.as_str())];
+        history.extend(ticket.transitions.iter().map(|t| (t.at, t.to_status.as_str())));
 
-    let mut entries = Vec::new();
+        for &(at, status) in &history {
+            let first = first_reached.entry(status).or_insert(at);
+            *first = (*first).min(at);
+        }
 
-    for priority in priorities {
-        // Calculate average
-        let avg_hours: f64 = conn
-            .query_row(
-                "SELECT AVG((julianday(resolved_at) - julianday(created_at)) * 24) FROM tickets WHERE priority = ?1 AND resolved_at IS NOT NULL",
-                [&priority],
-                |row| row.get(0),
-            )
-            .optional()
-            .map_err(DbError::from)?
-            .unwrap_or(0.0);
-
-        // Calculate median
-        let count: u32 = conn
-            .query_row(
-                "SELECT COUNT(*) FROM tickets WHERE priority = ?1 AND resolved_at IS NOT NULL",
-                [&priority],
-                |row| row.get(0),
-            )
-            .map_err(DbError::from)?;
+        let mut current = None;
+        let mut next = 0;
+        for (i, period) in periods.iter().enumerate() {
+            while next < history.len() && history[next].0.date_naive() < period.end {
+                current = Some(history[next].1);
+                next += 1;
+            }
+            if let Some(status) = current {
+                counts_by_status
+                    .entry(status)
+                    .or_insert_with(|| vec![0; periods.len()])[i] += 1;
+            }
+        }
+    }
 
-        let median_hours: f64 = if count > 0 {
-            conn.query_row(
-                r#"
-                SELECT (julianday(resolved_at) - julianday(created_at)) * 24
-                FROM tickets
-                WHERE priority = ?1 AND resolved_at IS NOT NULL
-                ORDER BY (julianday(resolved_at) - julianday(created_at))
-                LIMIT 1
-                OFFSET ?2
-                "#,
-                params![&priority, count / 2],
-                |row| row.get(0),
-            )
-            .optional()
-            .map_err(DbError::from)?
-            .unwrap_or(0.0)
-        } else {
-            0.0
+    let mut statuses: Vec<&str> = counts_by_status.keys().copied().collect();
+    statuses.sort_by_key(|status| (first_reached[status], *status));
+
+    periods
+        .iter()
+        .enumerate()
+        .map(|(i, period)| CumulativeFlowEntry {
+            date: period.label.clone(),
+            statuses: statuses
+                .iter()
+                .map(|status| CountEntry {
+                    name: status.to_string(),
+                    count: counts_by_status[status][i],
+                })
+                .collect(),
+        })
+        .collect()
+}
+
+/// Resolution time of a resolved ticket, in calendar and business hours
+struct ResolutionTime {
+    priority: String,
+    calendar_hours: f64,
+    business_hours: f64,
+}
+
+fn load_resolution_times(
+    conn: &Connection,
+    selection: &TicketSelection,
+    business_hours: &BusinessHoursSettings,
+    calendar: &WorkCalendar,
+) -> Result<Vec<ResolutionTime>, AppError> {
+    let (condition, params) = selection.condition();
+    let query = format!(
+        "SELECT priority, created_at, resolved_at FROM {} WHERE resolved_at IS NOT NULL AND {}",
+        selection.source, condition
+    );
+    let mut stmt = conn.prepare(&query).map_err(DbError::from)?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+            ))
+        })
+        .map_err(DbError::from)?;
+
+    let team_tz = business_hours.team_timezone()?;
+    let mut times = Vec::new();
+    for row in rows {
+        let (priority, created_at, resolved_at) = row.map_err(DbError::from)?;
+        // Jira's "+0000" offsets aren't understood by SQLite's julianday, so parse here instead
+        let (Some(created_at), Some(resolved_at)) = (
+            parse_jira_timestamp(&created_at),
+            parse_jira_timestamp(&resolved_at),
+        ) else {
+            continue;
         };
 
-        entries.push(AvgEntry {
-            name: priority,
-            avg_hours,
-            median_hours,
-            count,
+        let schedule = business_hours.schedule_for(&priority)?;
+
+        times.push(ResolutionTime {
+            calendar_hours: hours_between(created_at, resolved_at),
+            business_hours: business_hours_between_instants(
+                created_at,
+                resolved_at,
+                team_tz,
+                &schedule,
+                calendar,
+            )?,
+            priority,
         });
     }
 
+    Ok(times)
+}
+
+fn get_resolution_time_by_priority(times: &[ResolutionTime]) -> Vec<AvgEntry> {
+    let mut by_priority: HashMap<&str, Vec<&ResolutionTime>> = HashMap::new();
+    for time in times {
+        by_priority.entry(&time.priority).or_default().push(time);
+    }
+
+    let mut entries: Vec<AvgEntry> = by_priority
+        .into_iter()
+        .map(|(priority, times)| {
+            let calendar = flow_time_stats(times.iter().map(|t| t.calendar_hours).collect());
+            let business = flow_time_stats(times.iter().map(|t| t.business_hours).collect());
+            AvgEntry {
+                name: priority.to_string(),
+                avg_hours: calendar.avg_hours,
+                median_hours: calendar.median_hours,
+                business_avg_hours: business.avg_hours,
+                business_median_hours: business.median_hours,
+                count: calendar.count,
+            }
+        })
+        .collect();
+
     // Sort by priority order
     entries.sort_by_key(|e| match e.name.as_str() {
         "Critical" => 1,
@@ -227,62 +1047,291 @@ fn get_resolution_time_by_priority(conn: &Connection) -> Result<Vec<AvgEntry>, A
         _ => 5,
     });
 
-    Ok(entries)
+    entries
 }
 
-fn get_summary_stats(conn: &Connection) -> Result<SummaryStats, AppError> {
-    let total_tickets: u32 = conn
-        .query_row("SELECT COUNT(*) FROM tickets", [], |row| row.get(0))
-        .map_err(DbError::from)?;
+/// A ticket's timestamps and ordered status history, parsed for flow metrics
+struct FlowTicket {
+    status: String,
+    initial_status: String,
+    created_at: DateTime<FixedOffset>,
+    resolved_at: Option<DateTime<FixedOffset>>,
+    transitions: Vec<FlowTransition>,
+}
 
-    let open_tickets: u32 = conn
-        .query_row(
-            "SELECT COUNT(*) FROM tickets WHERE resolved_at IS NULL",
-            [],
-            |row| row.get(0),
+struct FlowTransition {
+    from_status: Option<String>,
+    to_status: String,
+    at: DateTime<FixedOffset>,
+}
+
+/// The selected tickets with their status history up to `now`
+fn load_flow_tickets(
+    conn: &Connection,
+    selection: &TicketSelection,
+    now: DateTime<FixedOffset>,
+) -> Result<Vec<FlowTicket>, AppError> {
+    let mut transitions_by_key: HashMap<String, Vec<FlowTransition>> = HashMap::new();
+
+    let mut stmt = conn
+        .prepare(
+            "SELECT jira_key, from_status, to_status, transitioned_at FROM status_transitions \
+             ORDER BY jira_key, id",
         )
         .map_err(DbError::from)?;
+    let rows = stmt
+        .query_map([], |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, Option<String>>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, String>(3)?,
+            ))
+        })
+        .map_err(DbError::from)?;
 
-    let resolved_tickets = total_tickets - open_tickets;
+    for row in rows {
+        let (jira_key, from_status, to_status, transitioned_at) = row.map_err(DbError::from)?;
+        if let Some(at) = parse_jira_timestamp(&transitioned_at).filter(|at| *at <= now) {
+            transitions_by_key
+                .entry(jira_key)
+                .or_default()
+                .push(FlowTransition {
+                    from_status,
+                    to_status,
+                    at,
+                });
+        }
+    }
+    // Timestamps keep the offset Jira reported them with, so they don't sort as text
+    for history in transitions_by_key.values_mut() {
+        history.sort_by_key(|t| t.at);
+    }
 
-    // Calculate average resolution time in calendar hours for resolved tickets
-    let avg_resolution_hours: f64 = conn
-        .query_row(
-            "SELECT AVG((julianday(resolved_at) - julianday(created_at)) * 24) FROM tickets WHERE resolved_at IS NOT NULL",
-            [],
-            |row| row.get(0),
-        )
-        .optional()
-        .map_err(DbError::from)?
-        .unwrap_or(0.0);
+    let (condition, params) = selection.condition();
+    let query = format!(
+        "SELECT jira_key, status, created_at, resolved_at FROM {} WHERE {}",
+        selection.source, condition
+    );
+    let mut stmt = conn.prepare(&query).map_err(DbError::from)?;
+    let rows = stmt
+        .query_map(params_from_iter(params), |row| {
+            Ok((
+                row.get::<_, String>(0)?,
+                row.get::<_, String>(1)?,
+                row.get::<_, String>(2)?,
+                row.get::<_, Option<String>>(3)?,
+            ))
+        })
+        .map_err(DbError::from)?;
 
-    // Calculate median resolution time (50th percentile)
-    let median_resolution_hours: f64 = conn
-        .query_row(
-            r#"
-            SELECT (julianday(resolved_at) - julianday(created_at)) * 24 as hours
-            FROM tickets
-            WHERE resolved_at IS NOT NULL
-            ORDER BY hours
-            LIMIT 1
-            OFFSET (SELECT COUNT(*) FROM tickets WHERE resolved_at IS NOT NULL) / 2
-            "#,
-            [],
-            |row| row.get(0),
-        )
-        .optional()
-        .map_err(DbError::from)?
-        .unwrap_or(0.0);
+    let mut tickets = Vec::new();
+    for row in rows {
+        let (jira_key, status, created_at, resolved_at) = row.map_err(DbError::from)?;
+        let Some(created_at) = parse_jira_timestamp(&created_at) else {
+            continue;
+        };
+        let transitions = transitions_by_key.remove(&jira_key).unwrap_or_default();
+        // Before its first transition the ticket sat in that transition's source status
+        let initial_status = transitions
+            .first()
+            .and_then(|t| t.from_status.clone())
+            .unwrap_or_else(|| status.clone());
+
+        tickets.push(FlowTicket {
+            status,
+            initial_status,
+            created_at,
+            resolved_at: resolved_at.as_deref().and_then(parse_jira_timestamp),
+            transitions,
+        });
+    }
+
+    Ok(tickets)
+}
+
+/// Hours spent in each status. Open tickets also count the time spent in their current status so far.
+fn get_time_in_status(tickets: &[FlowTicket], now: DateTime<FixedOffset>) -> Vec<StatusDurationEntry> {
+    let mut hours_by_status: HashMap<String, Vec<f64>> = HashMap::new();
+
+    for ticket in tickets {
+        let mut current_status = ticket.initial_status.clone();
+        let mut entered_at = ticket.created_at;
+
+        for transition in &ticket.transitions {
+            hours_by_status
+                .entry(current_status)
+                .or_default()
+                .push(hours_between(entered_at, transition.at));
+            current_status = transition.to_status.clone();
+            entered_at = transition.at;
+        }
+
+        if ticket.resolved_at.is_none() {
+            hours_by_status
+                .entry(current_status)
+                .or_default()
+                .push(hours_between(entered_at, now));
+        }
+    }
+
+    let mut entries: Vec<StatusDurationEntry> = hours_by_status
+        .into_iter()
+        .map(|(name, hours)| {
+            let stats = flow_time_stats(hours);
+            StatusDurationEntry {
+                name,
+                avg_hours: stats.avg_hours,
+                median_hours: stats.median_hours,
+                p90_hours: stats.p90_hours,
+                count: stats.count,
+            }
+        })
+        .collect();
+
+    entries.sort_by(|a, b| b.avg_hours.total_cmp(&a.avg_hours));
+    entries
+}
+
+/// Hours from the first move to "In Progress" until the final move to "Done", for tickets that are done
+fn get_cycle_time(tickets: &[FlowTicket]) -> FlowTimeStats {
+    let hours = tickets
+        .iter()
+        .filter(|t| t.status == DONE_STATUS)
+        .filter_map(|t| {
+            let started_at = t
+                .transitions
+                .iter()
+                .find(|tr| tr.to_status == IN_PROGRESS_STATUS)
+                .map(|tr| tr.at)?;
+            let done_at = t
+                .transitions
+                .iter()
+                .rev()
+                .find(|tr| tr.to_status == DONE_STATUS)
+                .map(|tr| tr.at)?;
+            (done_at >= started_at).then(|| hours_between(started_at, done_at))
+        })
+        .collect();
+
+    flow_time_stats(hours)
+}
+
+/// Hours from creation until resolution, for resolved tickets
+fn get_lead_time(tickets: &[FlowTicket]) -> FlowTimeStats {
+    let hours = tickets
+        .iter()
+        .filter_map(|t| t.resolved_at.map(|resolved| hours_between(t.created_at, resolved)))
+        .collect();
+
+    flow_time_stats(hours)
+}
+
+fn flow_time_stats(mut hours: Vec<f64>) -> FlowTimeStats {
+    if hours.is_empty() {
+        return FlowTimeStats {
+            avg_hours: 0.0,
+            median_hours: 0.0,
+            p90_hours: 0.0,
+            count: 0,
+        };
+    }
+
+    hours.sort_by(f64::total_cmp);
+    let count = hours.len();
+    // Nearest-rank percentile; the median matches the OFFSET count / 2 used by the SQL aggregations
+    let p90_index = ((count as f64 * 0.9).ceil() as usize).saturating_sub(1);
+
+    FlowTimeStats {
+        avg_hours: hours.iter().sum::<f64>() / count as f64,
+        median_hours: hours[count / 2],
+        p90_hours: hours[p90_index],
+        count: count as u32,
+    }
+}
+
+fn get_summary_stats(
+    conn: &Connection,
+    selection: &TicketSelection,
+    resolution_times: &[ResolutionTime],
+) -> Result<SummaryStats, AppError> {
+    let count = |selection: &TicketSelection, resolved: &str| -> Result<u32, AppError> {
+        let (condition, params) = selection.condition();
+        let query = format!(
+            "SELECT COUNT(*) FROM {} WHERE {} AND {}",
+            selection.source, resolved, condition
+        );
+        let count = conn
+            .query_row(&query, params_from_iter(&params), |row| row.get(0))
+            .map_err(DbError::from)?;
+        Ok(count)
+    };
+
+    let total_tickets = count(selection, "1")?;
+    let resolved_tickets = count(selection, "resolved_at IS NOT NULL")?;
+    // The open backlog includes tickets created before the range
+    let any_creation_date = TicketSelection {
+        created_from: None,
+        created_to: None,
+        ..*selection
+    };
+    let open_tickets = count(&any_creation_date, "resolved_at IS NULL")?;
+
+    let calendar = flow_time_stats(resolution_times.iter().map(|t| t.calendar_hours).collect());
+    let business = flow_time_stats(resolution_times.iter().map(|t| t.business_hours).collect());
 
     Ok(SummaryStats {
         total_tickets,
         open_tickets,
         resolved_tickets,
-        avg_resolution_hours,
-        median_resolution_hours,
+        avg_resolution_hours: calendar.avg_hours,
+        median_resolution_hours: calendar.median_hours,
+        avg_resolution_business_hours: business.avg_hours,
+        median_resolution_business_hours: business.median_hours,
     })
 }
 
+pub fn get_holidays(conn: &Connection) -> Result<Vec<Holiday>, AppError> {
+    let mut stmt = conn
+        .prepare("SELECT date, name FROM holidays ORDER BY date")
+        .map_err(DbError::from)?;
+
+    let rows = stmt
+        .query_map([], |row| Ok((row.get::<_, String>(0)?, row.get::<_, Option<String>>(1)?)))
+        .map_err(DbError::from)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(DbError::from)?;
+
+    // Skip rows that aren't valid dates rather than failing every aggregation
+    Ok(rows
+        .into_iter()
+        .filter_map(|(date, name)| {
+            NaiveDate::parse_from_str(&date, "%Y-%m-%d")
+                .ok()
+                .map(|date| Holiday { date, name })
+        })
+        .collect())
+}
+
+pub fn add_holidays(conn: &Connection, holidays: &[Holiday]) -> Result<(), AppError> {
+    let mut stmt = conn
+        .prepare("INSERT OR REPLACE INTO holidays (date, name) VALUES (?1, ?2)")
+        .map_err(DbError::from)?;
+
+    for holiday in holidays {
+        stmt.execute(params![holiday.date.to_string(), holiday.name])
+            .map_err(DbError::from)?;
+    }
+
+    Ok(())
+}
+
+pub fn delete_holiday(conn: &Connection, date: NaiveDate) -> Result<(), AppError> {
+    conn.execute("DELETE FROM holidays WHERE date = ?1", params![date.to_string()])
+        .map_err(DbError::from)?;
+    Ok(())
+}
+
 pub fn get_sync_metadata(conn: &Connection, key: &str) -> Result<Option<String>, AppError> {
     let result: Option<String> = conn
         .query_row(
@@ -303,3 +1352,737 @@ pub fn set_sync_metadata(conn: &Connection, key: &str, value: &str) -> Result<()
     .map_err(DbError::from)?;
     Ok(())
 }
+
+/// Records the start of a sync and returns its run id
+pub fn start_sync_run(
+    conn: &Connection,
+    trigger: SyncTrigger,
+    started_at: &str,
+) -> Result<i64, AppError> {
+    conn.execute(
+        "INSERT INTO sync_runs (trigger, status, started_at) VALUES (?1, 'running', ?2)",
+        params![trigger.as_str(), started_at],
+    )
+    .map_err(DbError::from)?;
+    Ok(conn.last_insert_rowid())
+}
+
+pub fn finish_sync_run(conn: &Connection, run: &SyncRun) -> Result<(), AppError> {
+    conn.execute(
+        "UPDATE sync_runs SET status = ?2, finished_at = ?3, duration_ms = ?4, fetched = ?5, \
+         inserted = ?6, updated = ?7, unchanged = ?8, out_of_scope = ?9, error = ?10 \
+         WHERE id = ?1",
+        params![
+            run.id,
+            run.status,
+            run.finished_at,
+            run.duration_ms,
+            run.fetched,
+            run.inserted,
+            run.updated,
+            run.unchanged,
+            run.out_of_scope,
+            run.error,
+        ],
+    )
+    .map_err(DbError::from)?;
+    Ok(())
+}
+
+/// The most recent sync runs, newest first
+pub fn get_sync_runs(conn: &Connection, limit: u32) -> Result<Vec<SyncRun>, AppError> {
+    let mut stmt = conn
+        .prepare(
+            "SELECT id, trigger, status, started_at, finished_at, duration_ms, fetched, \
+             inserted, updated, unchanged, out_of_scope, error \
+             FROM sync_runs ORDER BY id DESC LIMIT ?1",
+        )
+        .map_err(DbError::from)?;
+
+    let runs = stmt
+        .query_map(params![limit], |row| {
+            Ok(SyncRun {
+                id: row.get(0)?,
+                trigger: row.get(1)?,
+                status: row.get(2)?,
+                started_at: row.get(3)?,
+                finished_at: row.get(4)?,
+                duration_ms: row.get(5)?,
+                fetched: row.get(6)?,
+                inserted: row.get(7)?,
+                updated: row.get(8)?,
+                unchanged: row.get(9)?,
+                out_of_scope: row.get(10)?,
+                error: row.get(11)?,
+            })
+        })
+        .map_err(DbError::from)?
+        .collect::<Result<Vec<_>, _>>()
+        .map_err(DbError::from)?;
+
+    Ok(runs)
+}
+
+pub fn get_sync_checkpoint(
+    conn: &Connection,
+    scope_jql: &str,
+) -> Result<Option<SyncCheckpoint>, AppError> {
+    let checkpoint = conn
+        .query_row(
+            "SELECT scope_jql, jql, next_page_token, pages, tickets, max_updated, started_at \
+             FROM sync_checkpoints WHERE scope_jql = ?1",
+            params![scope_jql],
+            |row| {
+                Ok(SyncCheckpoint {
+                    scope_jql: row.get(0)?,
+                    jql: row.get(1)?,
+                    next_page_token: row.get(2)?,
+                    pages: row.get(3)?,
+                    tickets: row.get(4)?,
+                    max_updated: row.get(5)?,
+                    started_at: row.get(6)?,
+                })
+            },
+        )
+        .optional()
+        .map_err(DbError::from)?;
+    Ok(checkpoint)
+}
+
+pub fn save_sync_checkpoint(
+    conn: &Connection,
+    checkpoint: &SyncCheckpoint,
+) -> Result<(), AppError> {
+    conn.execute(
+        "INSERT OR REPLACE INTO sync_checkpoints \
+         (scope_jql, jql, next_page_token, pages, tickets, max_updated, started_at) \
+         VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7)",
+        params![
+            checkpoint.scope_jql,
+            checkpoint.jql,
+            checkpoint.next_page_token,
+            checkpoint.pages,
+            checkpoint.tickets,
+            checkpoint.max_updated,
+            checkpoint.started_at,
+        ],
+    )
+    .map_err(DbError::from)?;
+    Ok(())
+}
+
+pub fn delete_sync_checkpoint(conn: &Connection, scope_jql: &str) -> Result<(), AppError> {
+    conn.execute(
+        "DELETE FROM sync_checkpoints WHERE scope_jql = ?1",
+        params![scope_jql],
+    )
+    .map_err(DbError::from)?;
+    Ok(())
+}
+
+/// Marks runs left `running` by a crash or forced quit as failed
+pub fn fail_interrupted_sync_runs(conn: &Connection) -> Result<usize, AppError> {
+    let count = conn
+        .execute(
+            "UPDATE sync_runs SET status = 'failed', error = 'Interrupted' \
+             WHERE status = 'running'",
+            [],
+        )
+        .map_err(DbError::from)?;
+    Ok(count)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::initialize_database;
+    use crate::models::Granularity;
+
+    fn ticket(jira_key: &str, status: &str, created_at: &str, resolved_at: Option<&str>) -> Ticket {
+        Ticket {
+            id: 0,
+            jira_key: jira_key.to_string(),
+            summary: "Test ticket".to_string(),
+            status: status.to_string(),
+            priority: "Medium".to_string(),
+            issue_type: "Task".to_string(),
+            assignee: None,
+            reporter: None,
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            resolved_at: resolved_at.map(str::to_string),
+            labels: String::new(),
+            project_key: "TEST".to_string(),
+            category: None,
+        }
+    }
+
+    fn transition(jira_key: &str, from: &str, to: &str, at: &str) -> StatusTransition {
+        StatusTransition {
+            jira_key: jira_key.to_string(),
+            from_status: Some(from.to_string()),
+            to_status: to.to_string(),
+            author: None,
+            transitioned_at: at.to_string(),
+        }
+    }
+
+    fn test_db() -> Connection {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_database(&conn).unwrap();
+        conn
+    }
+
+    #[test]
+    fn test_time_in_status_and_cycle_time() {
+        let conn = test_db();
+        let done = ticket(
+            "TEST-1",
+            "Done",
+            "2025-01-06T08:00:00.000+0000",
+            Some("2025-01-06T20:00:00.000+0000"),
+        );
+        upsert_tickets(&conn, &[done]).unwrap();
+        replace_status_transitions(
+            &conn,
+            "TEST-1",
+            &[
+                transition("TEST-1", "Open", "In Progress", "2025-01-06T10:00:00.000+0000"),
+                transition("TEST-1", "In Progress", "Done", "2025-01-06T20:00:00.000+0000"),
+            ],
+        )
+        .unwrap();
+
+        let now = Utc::now().fixed_offset();
+        let tickets = load_flow_tickets(&conn, &TicketSelection::all(ACTIVE_TICKETS), now).unwrap();
+        let time_in_status = get_time_in_status(&tickets, now);
+        let in_progress = time_in_status.iter().find(|e| e.name == "In Progress").unwrap();
+        let open = time_in_status.iter().find(|e| e.name == "Open").unwrap();
+        assert_eq!(in_progress.avg_hours, 10.0);
+        assert_eq!(open.avg_hours, 2.0);
+        // Resolved tickets don't accumulate time in their final status
+        assert!(time_in_status.iter().all(|e| e.name != "Done"));
+
+        let cycle_time = get_cycle_time(&tickets);
+        assert_eq!(cycle_time.count, 1);
+        assert_eq!(cycle_time.avg_hours, 10.0);
+
+        let lead_time = get_lead_time(&tickets);
+        assert_eq!(lead_time.avg_hours, 12.0);
+    }
+
+    #[test]
+    fn test_resolution_time_in_business_hours() {
+        let conn = test_db();
+        // Monday 16:00 -> Tuesday 10:00
+        let resolved = ticket(
+            "TEST-1",
+            "Done",
+            "2025-01-06T16:00:00.000+0000",
+            Some("2025-01-07T10:00:00.000+0000"),
+        );
+        upsert_tickets(&conn, &[resolved]).unwrap();
+
+        let times = load_resolution_times(
+            &conn,
+            &TicketSelection::all(ACTIVE_TICKETS),
+            &BusinessHoursSettings::default(),
+            &WorkCalendar::default(),
+        )
+        .unwrap();
+        let by_priority = get_resolution_time_by_priority(&times);
+        assert_eq!(by_priority.len(), 1);
+        assert_eq!(by_priority[0].avg_hours, 18.0);
+        assert_eq!(by_priority[0].business_avg_hours, 2.0);
+
+        let all = TicketSelection::all(ACTIVE_TICKETS);
+        let summary = get_summary_stats(&conn, &all, &times).unwrap();
+        assert_eq!(summary.resolved_tickets, 1);
+        assert_eq!(summary.median_resolution_business_hours, 2.0);
+    }
+
+    #[test]
+    fn test_summary_counts_older_open_tickets() {
+        let conn = test_db();
+        let tickets = [
+            ticket("TEST-1", "Open", "2023-03-01T08:00:00.000+0000", None),
+            ticket("TEST-2", "Open", "2025-01-06T08:00:00.000+0000", None),
+            ticket(
+                "TEST-3",
+                "Done",
+                "2025-01-06T08:00:00.000+0000",
+                Some("2025-01-07T08:00:00.000+0000"),
+            ),
+        ];
+        upsert_tickets(&conn, &tickets).unwrap();
+
+        let selection = TicketSelection {
+            created_from: NaiveDate::from_ymd_opt(2025, 1, 1),
+            created_to: NaiveDate::from_ymd_opt(2025, 1, 31),
+            ..TicketSelection::all(ACTIVE_TICKETS)
+        };
+        let summary = get_summary_stats(&conn, &selection, &[]).unwrap();
+        assert_eq!(summary.total_tickets, 2);
+        assert_eq!(summary.resolved_tickets, 1);
+        // The ticket open since 2023 is still part of the backlog
+        assert_eq!(summary.open_tickets, 2);
+    }
+
+    #[test]
+    fn test_reconcile_scope_tombstones_and_restores() {
+        let conn = test_db();
+        let tickets: Vec<Ticket> = ["TEST-1", "TEST-2"]
+            .iter()
+            .map(|key| ticket(key, "Open", "2025-01-06T08:00:00Z", None))
+            .collect();
+        upsert_tickets(&conn, &tickets).unwrap();
+        let all = TicketSelection::all(ACTIVE_TICKETS);
+
+        let tombstoned =
+            reconcile_scope(&conn, &["TEST-1".to_string()], "2025-01-07T00:00:00Z").unwrap();
+        assert_eq!(tombstoned, 1);
+        assert_eq!(get_summary_stats(&conn, &all, &[]).unwrap().open_tickets, 1);
+        assert_eq!(get_tickets(&conn).unwrap().len(), 1);
+
+        // Tickets coming back into scope are restored, not duplicated
+        let tombstoned = reconcile_scope(
+            &conn,
+            &["TEST-1".to_string(), "TEST-2".to_string()],
+            "2025-01-08T00:00:00Z",
+        )
+        .unwrap();
+        assert_eq!(tombstoned, 0);
+        assert_eq!(get_summary_stats(&conn, &all, &[]).unwrap().open_tickets, 2);
+    }
+
+    #[test]
+    fn test_upsert_reports_outcome() {
+        let conn = test_db();
+        let open = ticket("TEST-1", "Open", "2025-01-06T08:00:00Z", None);
+        let mut updated = open.clone();
+        updated.updated_at = "2025-01-07T08:00:00Z".to_string();
+        let new = ticket("TEST-2", "Open", "2025-01-06T08:00:00Z", None);
+
+        let outcomes = upsert_tickets(&conn, std::slice::from_ref(&open)).unwrap();
+        assert_eq!(outcomes, vec![UpsertOutcome::Inserted]);
+
+        // One outcome per ticket, in order
+        let outcomes = upsert_tickets(&conn, &[open, new.clone(), updated]).unwrap();
+        assert_eq!(
+            outcomes,
+            vec![UpsertOutcome::Unchanged, UpsertOutcome::Inserted, UpsertOutcome::Updated]
+        );
+        assert_eq!(upsert_tickets(&conn, &[new]).unwrap(), vec![UpsertOutcome::Unchanged]);
+    }
+
+    #[test]
+    fn test_swap_staged_tickets() {
+        let conn = test_db();
+        let created = "2025-01-06T08:00:00.000+0000";
+        let mut categorized = ticket("TEST-1", "Open", created, None);
+        categorized.category = Some("Printing".to_string());
+        let stored = [
+            categorized,
+            ticket("TEST-2", "Open", created, None),
+            ticket("TEST-3", "Open", created, None),
+        ];
+        upsert_tickets(&conn, &stored).unwrap();
+        replace_status_transitions(
+            &conn,
+            "TEST-1",
+            &[transition("TEST-1", "To Do", "Open", created)],
+        )
+        .unwrap();
+
+        // TEST-1 is fixed in Jira without a new `updated` and no longer matches its category
+        // rule, TEST-2 is unchanged, TEST-3 is gone
+        let mut fixed = ticket("TEST-1", "In Progress", created, None);
+        fixed.summary = "Fixed summary".to_string();
+        stage_tickets(
+            &conn,
+            &[
+                fixed,
+                ticket("TEST-2", "Open", created, None),
+                ticket("TEST-4", "Open", created, None),
+            ],
+            &[transition("TEST-1", "Open", "In Progress", created)],
+        )
+        .unwrap();
+        let counts = swap_staged_tickets(&conn, "2025-01-07").unwrap();
+
+        assert_eq!(
+            counts,
+            ResyncCounts {
+                inserted: 1,
+                updated: 1,
+                unchanged: 1,
+                removed: 1,
+            }
+        );
+        let tickets = get_tickets(&conn).unwrap();
+        let keys: Vec<&str> = tickets.iter().map(|t| t.jira_key.as_str()).collect();
+        assert_eq!(keys.len(), 3);
+        assert!(!keys.contains(&"TEST-3"));
+
+        let test_1 = tickets.iter().find(|t| t.jira_key == "TEST-1").unwrap();
+        assert_eq!(test_1.summary, "Fixed summary");
+        assert_eq!(test_1.category, None);
+        let transitions = get_status_transitions_by_key(&conn).unwrap();
+        assert_eq!(transitions["TEST-1"].len(), 1);
+        assert_eq!(transitions["TEST-1"][0].to_status, "In Progress");
+
+        let staged: i64 = conn
+            .query_row("SELECT COUNT(*) FROM tickets_staging", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(staged, 0);
+    }
+
+    #[test]
+    fn test_aggregations_as_of() {
+        let conn = test_db();
+        let at = |ts: &str| parse_jira_timestamp(ts).unwrap();
+        let counts = |entries: &[CountEntry]| -> Vec<(String, u32)> {
+            entries.iter().map(|e| (e.name.clone(), e.count)).collect()
+        };
+
+        // TEST-1 is raised to High on the 8th and resolved on the 10th; TEST-2 opens on the 9th
+        let mut escalated = ticket("TEST-1", "Open", "2025-01-06T08:00:00.000+0000", None);
+        upsert_tickets(&conn, std::slice::from_ref(&escalated)).unwrap();
+        escalated.priority = "High".to_string();
+        escalated.updated_at = "2025-01-08T08:00:00.000+0000".to_string();
+        upsert_tickets(&conn, std::slice::from_ref(&escalated)).unwrap();
+        escalated.status = "Done".to_string();
+        escalated.resolved_at = Some("2025-01-10T08:00:00.000+0000".to_string());
+        escalated.updated_at = "2025-01-10T08:00:00.000+0000".to_string();
+        upsert_tickets(&conn, std::slice::from_ref(&escalated)).unwrap();
+        upsert_tickets(&conn, &[escalated]).unwrap();
+        replace_status_transitions(
+            &conn,
+            "TEST-1",
+            &[transition("TEST-1", "Open", "Done", "2025-01-10T08:00:00.000+0000")],
+        )
+        .unwrap();
+        upsert_tickets(&conn, &[ticket("TEST-2", "Open", "2025-01-09T08:00:00.000+0000", None)])
+            .unwrap();
+
+        // Unchanged upserts don't add snapshots
+        let snapshots: i64 = conn
+            .query_row("SELECT COUNT(*) FROM ticket_snapshots", [], |row| row.get(0))
+            .unwrap();
+        assert_eq!(snapshots, 4);
+
+        let settings = BusinessHoursSettings::default();
+        let query = DashboardQuery::default();
+        let filter = TicketFilter::default();
+        let aggregate_as_of = |as_of| {
+            get_aggregations(&conn, &settings, &[], &query, &filter, Some(at(as_of))).unwrap()
+        };
+        let before = aggregate_as_of("2025-01-07T00:00:00Z");
+        assert_eq!(before.summary.total_tickets, 1);
+        assert_eq!(before.summary.open_tickets, 1);
+        assert_eq!(counts(&before.tickets_by_priority), vec![("Medium".to_string(), 1)]);
+
+        let during = aggregate_as_of("2025-01-09T12:00:00Z");
+        assert_eq!(during.summary.total_tickets, 2);
+        assert_eq!(during.summary.open_tickets, 2);
+        assert_eq!(counts(&during.tickets_by_status), vec![("Open".to_string(), 2)]);
+        assert!(counts(&during.tickets_by_priority).contains(&("High".to_string(), 1)));
+
+        let after = aggregate_as_of("2025-01-11T00:00:00Z");
+        assert_eq!(after.summary.open_tickets, 1);
+        assert_eq!(after.summary.resolved_tickets, 1);
+        assert!(counts(&after.tickets_by_status).contains(&("Done".to_string(), 1)));
+
+        let since_2025 = DashboardQuery {
+            from: NaiveDate::from_ymd_opt(2025, 1, 1),
+            ..DashboardQuery::default()
+        };
+        let live = get_aggregations(&conn, &settings, &[], &since_2025, &filter, None).unwrap();
+        assert_eq!(live.summary.total_tickets, 2);
+    }
+
+    #[test]
+    fn test_tickets_over_time_counts_resolutions_when_they_happen() {
+        let conn = test_db();
+        let tickets = [
+            ticket(
+                "TEST-1",
+                "Done",
+                "2024-11-20T08:00:00.000+0000",
+                Some("2025-01-06T08:00:00.000+0000"),
+            ),
+            ticket("TEST-2", "Open", "2024-12-02T08:00:00.000+0000", None),
+        ];
+        upsert_tickets(&conn, &tickets).unwrap();
+
+        let now = parse_jira_timestamp("2025-01-15T00:00:00Z").unwrap();
+        let all = TicketSelection::all(ACTIVE_TICKETS);
+        let flow_tickets = load_flow_tickets(&conn, &all, now).unwrap();
+        let periods = Granularity::Month.periods(
+            NaiveDate::from_ymd_opt(2024, 11, 1).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 15).unwrap(),
+            MAX_PERIODS,
+        );
+        let series: Vec<(String, u32, u32, u32)> =
+            get_tickets_over_time(&flow_tickets, &periods.unwrap())
+            .into_iter()
+            .map(|e| (e.date, e.created, e.resolved, e.open))
+            .collect();
+
+        assert_eq!(
+            series,
+            vec![
+                ("2024-11".to_string(), 1, 0, 1),
+                ("2024-12".to_string(), 1, 0, 2),
+                ("2025-01".to_string(), 0, 1, 1),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_cumulative_flow_follows_transitions() {
+        let conn = test_db();
+        let tickets = [
+            ticket("TEST-1", "Done", "2025-01-06T08:00:00.000+0000", None),
+            ticket("TEST-2", "Open", "2025-01-07T08:00:00.000+0000", None),
+        ];
+        upsert_tickets(&conn, &tickets).unwrap();
+        replace_status_transitions(
+            &conn,
+            "TEST-1",
+            &[
+                transition("TEST-1", "Open", "In Progress", "2025-01-07T09:00:00.000+0000"),
+                transition("TEST-1", "In Progress", "Done", "2025-01-08T09:00:00.000+0000"),
+            ],
+        )
+        .unwrap();
+
+        let now = parse_jira_timestamp("2025-01-08T12:00:00Z").unwrap();
+        let all = TicketSelection::all(ACTIVE_TICKETS);
+        let flow_tickets = load_flow_tickets(&conn, &all, now).unwrap();
+        let periods = Granularity::Day.periods(
+            NaiveDate::from_ymd_opt(2025, 1, 5).unwrap(),
+            NaiveDate::from_ymd_opt(2025, 1, 8).unwrap(),
+            MAX_PERIODS,
+        );
+        let flow = get_cumulative_flow(&flow_tickets, &periods.unwrap());
+        let days: Vec<(String, Vec<u32>)> = flow
+            .into_iter()
+            .map(|e| (e.date, e.statuses.iter().map(|s| s.count).collect()))
+            .collect();
+
+        // Open, In Progress, Done
+        assert_eq!(
+            days,
+            vec![
+                ("2025-01-05".to_string(), vec![0, 0, 0]),
+                ("2025-01-06".to_string(), vec![1, 0, 0]),
+                ("2025-01-07".to_string(), vec![1, 1, 0]),
+                ("2025-01-08".to_string(), vec![1, 0, 1]),
+            ]
+        );
+    }
+
+    #[test]
+    fn test_aggregations_cover_query_range() {
+        let conn = test_db();
+        let tickets = [
+            ticket("TEST-1", "Open", "2024-03-04T08:00:00.000+0000", None),
+            ticket(
+                "TEST-2",
+                "Done",
+                "2025-01-07T08:00:00.000+0000",
+                Some("2025-01-21T08:00:00.000+0000"),
+            ),
+        ];
+        upsert_tickets(&conn, &tickets).unwrap();
+
+        let query = DashboardQuery {
+            from: NaiveDate::from_ymd_opt(2025, 1, 1),
+            to: NaiveDate::from_ymd_opt(2025, 1, 31),
+            granularity: Granularity::Week,
+        };
+        let settings = BusinessHoursSettings::default();
+        let filter = TicketFilter::default();
+        let result = get_aggregations(&conn, &settings, &[], &query, &filter, None).unwrap();
+
+        // Only tickets created in the range are counted
+        assert_eq!(result.summary.total_tickets, 1);
+        assert_eq!(result.summary.resolved_tickets, 1);
+
+        // Weeks without activity are still listed, and older open tickets stay open
+        let series: Vec<(String, u32, u32, u32)> = result
+            .tickets_over_time
+            .into_iter()
+            .map(|e| (e.date, e.created, e.resolved, e.open))
+            .collect();
+        assert_eq!(
+            series,
+            vec![
+                ("2025-W01".to_string(), 0, 0, 1),
+                ("2025-W02".to_string(), 1, 0, 2),
+                ("2025-W03".to_string(), 0, 0, 2),
+                ("2025-W04".to_string(), 0, 1, 1),
+                ("2025-W05".to_string(), 0, 0, 1),
+            ]
+        );
+        assert_eq!(result.cumulative_flow.len(), 5);
+
+        let backwards = DashboardQuery {
+            from: query.to,
+            to: query.from,
+            granularity: Granularity::Day,
+        };
+        assert!(get_aggregations(&conn, &settings, &[], &backwards, &filter, None).is_err());
+
+        let decades_of_days = DashboardQuery {
+            from: NaiveDate::from_ymd_opt(1970, 1, 1),
+            to: query.to,
+            granularity: Granularity::Day,
+        };
+        let result = get_aggregations(&conn, &settings, &[], &decades_of_days, &filter, None);
+        assert!(matches!(result, Err(AppError::Config(_))));
+        let unbounded = DashboardQuery {
+            to: Some(NaiveDate::MAX),
+            granularity: Granularity::Quarter,
+            ..query
+        };
+        let result = get_aggregations(&conn, &settings, &[], &unbounded, &filter, None);
+        assert!(matches!(result, Err(AppError::Config(_))));
+    }
+
+    #[test]
+    fn test_ticket_filter() {
+        let conn = test_db();
+        let mut api = ticket("API-1", "Open", "2025-01-06T08:00:00.000+0000", None);
+        api.project_key = "API".to_string();
+        api.assignee = Some("alice".to_string());
+        api.labels = "backend,urgent".to_string();
+        let mut web = ticket("WEB-1", "Open", "2025-01-06T09:00:00.000+0000", None);
+        web.project_key = "WEB".to_string();
+        web.labels = "frontend".to_string();
+        web.category = Some("Bug".to_string());
+        let mut done = ticket(
+            "WEB-2",
+            "Done",
+            "2025-01-06T10:00:00.000+0000",
+            Some("2025-01-07T10:00:00.000+0000"),
+        );
+        done.project_key = "WEB".to_string();
+        done.labels = "urgent-ish".to_string();
+        upsert_tickets(&conn, &[api, web, done]).unwrap();
+
+        let set = |values: &[&str]| values.iter().map(|v| v.to_string()).collect();
+        let keys = |filter: &TicketFilter| -> Vec<String> {
+            let mut keys: Vec<String> = get_tickets_matching(&conn, filter)
+                .unwrap()
+                .into_iter()
+                .map(|t| t.jira_key)
+                .collect();
+            keys.sort();
+            keys
+        };
+
+        let mut filter = TicketFilter::default();
+        filter.projects.include = set(&["WEB"]);
+        assert_eq!(keys(&filter), vec!["WEB-1", "WEB-2"]);
+        filter.statuses.exclude = set(&["Done"]);
+        assert_eq!(keys(&filter), vec!["WEB-1"]);
+
+        // Whole labels only, and tickets without an assignee aren't excluded by name
+        let mut filter = TicketFilter::default();
+        filter.labels.include = set(&["urgent"]);
+        assert_eq!(keys(&filter), vec!["API-1"]);
+        filter.labels.include = set(&[]);
+        filter.assignees.exclude = set(&["alice"]);
+        assert_eq!(keys(&filter), vec!["WEB-1", "WEB-2"]);
+
+        let mut filter = TicketFilter::default();
+        filter.assignees.include = set(&["Unassigned"]);
+        assert_eq!(keys(&filter), vec!["WEB-1", "WEB-2"]);
+        filter.assignees.include = set(&[]);
+        filter.assignees.exclude = set(&["Unassigned"]);
+        assert_eq!(keys(&filter), vec!["API-1"]);
+
+        let mut filter = TicketFilter::default();
+        filter.categories.include = set(&["Uncategorized", "' OR 1 = 1 --"]);
+        assert_eq!(keys(&filter), vec!["API-1", "WEB-2"]);
+
+        let query = DashboardQuery {
+            from: NaiveDate::from_ymd_opt(2025, 1, 1),
+            to: NaiveDate::from_ymd_opt(2025, 1, 31),
+            granularity: Granularity::Month,
+        };
+        let settings = BusinessHoursSettings::default();
+        let result = get_aggregations(&conn, &settings, &[], &query, &filter, None).unwrap();
+        assert_eq!(result.summary.total_tickets, 2);
+        assert_eq!(result.summary.resolved_tickets, 1);
+        assert_eq!(result.tickets_over_time.iter().map(|e| e.created).sum::<u32>(), 2);
+    }
+
+    #[test]
+    fn test_transitions_ordered_across_offsets() {
+        let conn = test_db();
+        upsert_tickets(&conn, &[ticket("TEST-1", "Done", "2025-01-06T07:00:00.000+0000", None)])
+            .unwrap();
+        // 08:45+0000 sorts before 09:30+0100 as text but happened after it
+        replace_status_transitions(
+            &conn,
+            "TEST-1",
+            &[
+                transition("TEST-1", "In Progress", "Done", "2025-01-06T08:45:00.000+0000"),
+                transition("TEST-1", "Open", "In Progress", "2025-01-06T09:30:00.000+0100"),
+            ],
+        )
+        .unwrap();
+
+        let by_key = get_status_transitions_by_key(&conn).unwrap();
+        let statuses: Vec<&str> = by_key["TEST-1"].iter().map(|t| t.to_status.as_str()).collect();
+        assert_eq!(statuses, vec!["In Progress", "Done"]);
+
+        let now = parse_jira_timestamp("2025-01-07T00:00:00Z").unwrap();
+        let all = TicketSelection::all(ACTIVE_TICKETS);
+        let flow_tickets = load_flow_tickets(&conn, &all, now).unwrap();
+        assert_eq!(flow_tickets[0].initial_status, "Open");
+        assert_eq!(flow_tickets[0].transitions[1].to_status, "Done");
+    }
+
+    #[test]
+    fn test_sync_run_history() {
+        let conn = test_db();
+        let first = start_sync_run(&conn, SyncTrigger::Scheduled, "2025-01-06T08:00:00Z").unwrap();
+        let second = start_sync_run(&conn, SyncTrigger::Manual, "2025-01-06T09:00:00Z").unwrap();
+
+        let mut run = get_sync_runs(&conn, 10).unwrap().remove(0);
+        assert_eq!(run.id, second);
+        assert_eq!(run.status, "running");
+        run.status = "succeeded".to_string();
+        run.fetched = 3;
+        run.inserted = 2;
+        run.unchanged = 1;
+        finish_sync_run(&conn, &run).unwrap();
+
+        assert_eq!(fail_interrupted_sync_runs(&conn).unwrap(), 1);
+
+        let runs = get_sync_runs(&conn, 10).unwrap();
+        assert_eq!(runs.len(), 2);
+        assert_eq!((runs[0].status.as_str(), runs[0].inserted), ("succeeded", 2));
+        assert_eq!(runs[1].id, first);
+        assert_eq!(runs[1].trigger, "scheduled");
+        assert_eq!(runs[1].error.as_deref(), Some("Interrupted"));
+        assert_eq!(get_sync_runs(&conn, 1).unwrap().len(), 1);
+    }
+
+    #[test]
+    fn test_flow_time_stats_percentiles() {
+        let stats = flow_time_stats((1..=10).map(f64::from).collect());
+        assert_eq!(stats.count, 10);
+        assert_eq!(stats.avg_hours, 5.5);
+        assert_eq!(stats.median_hours, 6.0);
+        assert_eq!(stats.p90_hours, 9.0);
+
+        let empty = flow_time_stats(Vec::new());
+        assert_eq!(empty.count, 0);
+        assert_eq!(empty.p90_hours, 0.0);
+    }
+}
@@ -1,3 +1,4 @@
+use chrono::{NaiveTime, Weekday};
 use thiserror::Error;
 
 #[derive(Error, Debug)]
@@ -14,9 +15,15 @@ pub enum AppError {
     #[error("Sync is already in progress")]
     SyncAlreadyInProgress,
 
+    #[error("Sync was cancelled")]
+    SyncCancelled,
+
     #[error("Configuration error: {0}")]
     Config(String),
 
+    #[error("Invalid work schedule: {0}")]
+    Schedule(#[from] ScheduleError),
+
     #[error("Internal error: {0}")]
     Internal(String),
 }
@@ -62,4 +69,30 @@ pub enum JiraError {
 
     #[error("Not configured. Set Jira URL, email, and API token in Settings.")]
     NotConfigured,
+
+    #[error("Invalid JQL: {0}")]
+    InvalidJql(String),
+}
+
+#[derive(Error, Debug)]
+pub enum ScheduleError {
+    #[error("work hours start={start}, end={end} must be 0-23")]
+    InvalidHours { start: u32, end: u32 },
+
+    #[error("work start hour ({start}) must be less than work end hour ({end})")]
+    StartNotBeforeEnd { start: u32, end: u32 },
+
+    #[error("interval {start}-{end} on {weekday} must end after it starts")]
+    EmptyInterval {
+        weekday: Weekday,
+        start: NaiveTime,
+        end: NaiveTime,
+    },
+
+    #[error("intervals on {weekday} overlap ({first_end} is after {second_start})")]
+    OverlappingIntervals {
+        weekday: Weekday,
+        first_end: NaiveTime,
+        second_start: NaiveTime,
+    },
 }
@@ -1,12 +1,37 @@
 use crate::errors::{AppError, JiraError};
-use crate::jira::types::JiraSearchResponse;
-use crate::models::Ticket;
+use crate::jira::retry::{RetryListener, RetryPolicy, RetryWait};
+use crate::jira::types::{
+    JiraApproximateCount, JiraChangelogHistory, JiraChangelogPage, JiraKeySearchResponse,
+    JiraMyself, JiraSearchResponse, JqlParseResponse,
+};
+use crate::models::{StatusTransition, Ticket};
+use crate::services::time_calc::parse_jira_timestamp;
+use crate::services::CancellationToken;
 use base64::Engine;
+use futures_util::stream::{self, Stream};
+use regex::Regex;
+use serde::de::DeserializeOwned;
+use std::future::Future;
+use std::time::Duration;
+
+/// Scope used when the user hasn't configured one
+pub const DEFAULT_SCOPE_JQL: &str = "assignee = currentUser()";
+
+/// One page of search results. `next_page_token` is `None` on the last page.
+pub struct TicketPage {
+    pub tickets: Vec<Ticket>,
+    pub transitions: Vec<StatusTransition>,
+    pub next_page_token: Option<String>,
+}
 
 pub struct JiraClient {
     base_url: String,
+    email: String,
     auth_header: String,
     client: reqwest::Client,
+    retry_policy: RetryPolicy,
+    retry_listener: Option<RetryListener>,
+    cancel: Option<CancellationToken>,
 }
 
 impl JiraClient {
@@ -17,48 +42,234 @@ impl JiraClient {
 
         Ok(JiraClient {
             base_url,
+            email: email.to_string(),
             auth_header,
             client,
+            retry_policy: RetryPolicy::default(),
+            retry_listener: None,
+            cancel: None,
         })
     }
 
+    /// Identifies the Jira site and user the client signs in as, e.g. to cache per-user data
+    pub fn account(&self) -> String {
+        format!("{} at {}", self.email, self.base_url)
+    }
+
+    /// Reports the countdown while waiting to retry a rate-limited or failed request
+    pub fn with_retry_listener(mut self, listener: RetryListener) -> Self {
+        self.retry_listener = Some(listener);
+        self
+    }
+
+    /// Stops waiting to retry a request once `cancel` is cancelled
+    pub fn with_cancellation(mut self, cancel: CancellationToken) -> Self {
+        self.cancel = Some(cancel);
+        self
+    }
+
     fn create_auth_header(email: &str, token: &str) -> String {
         let credentials = format!("{}:{}", email, token);
         let encoded = base64::engine::general_purpose::STANDARD.encode(credentials);
         format!("Basic {}", encoded)
     }
 
-    pub async fn fetch_tickets(
+    /// Streams the pages of `jql` results starting at `page_token`, one request per page,
+    /// so callers can save each page before the next one is fetched
+    pub fn ticket_pages<'a>(
+        &'a self,
+        jql: &'a str,
+        page_token: Option<String>,
+        cancel: &'a CancellationToken,
+    ) -> impl Stream<Item = Result<TicketPage, AppError>> + 'a {
+        // `None` once the last page has been returned
+        stream::try_unfold(Some(page_token), move |state| async move {
+            let Some(page_token) = state else {
+                return Ok(None);
+            };
+            cancel.check()?;
+            let page = self.fetch_ticket_page(jql, page_token.as_deref()).await?;
+            let next = page.next_page_token.clone().map(Some);
+            Ok(Some((page, next)))
+        })
+    }
+
+    /// Jira's estimate of how many issues match `jql`, cheap enough to ask before a sync
+    pub async fn approximate_count(&self, jql: &str) -> Result<u64, AppError> {
+        let url = format!("{}/search/approximate-count", self.base_url);
+        let body = serde_json::json!({ "jql": strip_order_by(jql) });
+        let response: JiraApproximateCount = self
+            .with_retry(|| async {
+                let response = self
+                    .client
+                    .post(&url)
+                    .header("Authorization", &self.auth_header)
+                    .header("Content-Type", "application/json")
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(JiraError::from)?;
+                Self::parse_response(response).await
+            })
+            .await?;
+
+        Ok(response.count)
+    }
+
+    /// Fetches the page of `jql` results starting at `page_token`, with full status histories
+    pub async fn fetch_ticket_page(
         &self,
-        last_sync_ts: Option<&str>,
-    ) -> Result<Vec<Ticket>, AppError> {
-        let mut all_tickets = Vec::new();
-        let mut next_page_token: Option<String> = None;
+        jql: &str,
+        page_token: Option<&str>,
+    ) -> Result<TicketPage, AppError> {
+        let response = self.search_jql(jql, page_token).await?;
+        let mut tickets = Vec::with_capacity(response.issues.len());
+        let mut transitions = Vec::new();
 
-        loop {
-            let jql = if let Some(ts) = last_sync_ts {
-                format!(
-                    "assignee = currentUser() AND updated >= \"{}\" ORDER BY updated ASC",
-                    ts
-                )
-            } else {
-                "assignee = currentUser() ORDER BY created DESC".to_string()
+        for mut issue in response.issues {
+            // The expanded changelog only holds the most recent entries; page through
+            // the dedicated endpoint when Jira tells us there is more
+            let histories = match issue.changelog.take() {
+                Some(changelog) if changelog.histories.len() as u32 >= changelog.total => {
+                    changelog.histories
+                }
+                _ => self.fetch_changelog(&issue.key).await?,
             };
 
-            let response = self.search_jql(&jql, next_page_token.as_deref()).await?;
+            transitions.extend(Self::extract_status_transitions(&issue.key, histories));
+            tickets.push(Self::convert_issue_to_ticket(issue));
+        }
+
+        Ok(TicketPage {
+            tickets,
+            transitions,
+            next_page_token: response.next_page_token,
+        })
+    }
 
-            for issue in response.issues {
-                let ticket = Self::convert_issue_to_ticket(issue);
-                all_tickets.push(ticket);
+    /// Keys of every issue currently matching the scope, used to detect deleted or moved tickets
+    pub async fn fetch_scope_keys(
+        &self,
+        scope_jql: &str,
+        cancel: &CancellationToken,
+    ) -> Result<Vec<String>, AppError> {
+        let mut keys = Vec::new();
+        let mut next_page_token: Option<String> = None;
+        let jql = build_search_jql(scope_jql, None);
+
+        loop {
+            cancel.check()?;
+            let mut body = serde_json::json!({
+                "jql": jql,
+                "maxResults": 5000,
+                "fields": []
+            });
+            if let Some(token) = next_page_token.as_deref() {
+                body["nextPageToken"] = serde_json::json!(token);
             }
 
+            let response: JiraKeySearchResponse = self.post_search(&body).await?;
+            keys.extend(response.issues.into_iter().map(|issue| issue.key));
+
             if response.next_page_token.is_none() {
                 break;
             }
             next_page_token = response.next_page_token;
         }
 
-        Ok(all_tickets)
+        Ok(keys)
+    }
+
+    /// Checks the URL and credentials with a single cheap request
+    pub async fn verify_connection(&self) -> Result<(), AppError> {
+        self.fetch_myself().await.map(|_| ())
+    }
+
+    /// Timezone of the authenticated user, which Jira uses to interpret dates in JQL
+    pub async fn fetch_user_timezone(&self) -> Result<Option<String>, AppError> {
+        Ok(self.fetch_myself().await?.time_zone)
+    }
+
+    async fn fetch_myself(&self) -> Result<JiraMyself, AppError> {
+        let url = format!("{}/myself", self.base_url);
+        self.with_retry(|| async {
+            let response = self
+                .client
+                .get(&url)
+                .header("Authorization", &self.auth_header)
+                .send()
+                .await
+                .map_err(JiraError::from)?;
+            Self::parse_response(response).await
+        })
+        .await
+    }
+
+    /// Checks a JQL query with Jira's strict parser, returning its errors if it is invalid
+    pub async fn validate_jql(&self, jql: &str) -> Result<(), AppError> {
+        let url = format!("{}/jql/parse?validation=strict", self.base_url);
+        let body = serde_json::json!({ "queries": [jql] });
+        let parsed: JqlParseResponse = self
+            .with_retry(|| async {
+                let response = self
+                    .client
+                    .post(&url)
+                    .header("Authorization", &self.auth_header)
+                    .header("Content-Type", "application/json")
+                    .json(&body)
+                    .send()
+                    .await
+                    .map_err(JiraError::from)?;
+                Self::parse_response(response).await
+            })
+            .await?;
+        let errors: Vec<String> = parsed
+            .queries
+            .into_iter()
+            .flat_map(|q| q.errors)
+            .collect();
+
+        if errors.is_empty() {
+            Ok(())
+        } else {
+            Err(JiraError::InvalidJql(errors.join("; ")).into())
+        }
+    }
+
+    async fn fetch_changelog(&self, issue_key: &str) -> Result<Vec<JiraChangelogHistory>, AppError> {
+        let mut histories = Vec::new();
+
+        loop {
+            let url = format!(
+                "{}/issue/{}/changelog?startAt={}&maxResults=100",
+                self.base_url,
+                issue_key,
+                histories.len()
+            );
+            let page: JiraChangelogPage = self
+                .with_retry(|| async {
+                    let response = self
+                        .client
+                        .get(&url)
+                        .header("Authorization", &self.auth_header)
+                        .send()
+                        .await
+                        .map_err(JiraError::from)?;
+                    Self::parse_response(response).await
+                })
+                .await?;
+            let page_len = page.values.len();
+            histories.extend(page.values);
+
+            if page.is_last.unwrap_or(false)
+                || page_len == 0
+                || histories.len() as u32 >= page.total
+            {
+                break;
+            }
+        }
+
+        Ok(histories)
     }
 
     async fn search_jql(
@@ -73,32 +284,103 @@ impl JiraClient {
                 "summary", "status", "priority", "issuetype",
                 "assignee", "reporter", "created", "updated",
                 "resolutiondate", "labels", "project"
-            ]
+            ],
+            "expand": "changelog"
         });
 
         if let Some(token) = next_page_token {
             body["nextPageToken"] = serde_json::json!(token);
         }
 
+        self.post_search(&body).await
+    }
+
+    async fn post_search<T: DeserializeOwned>(&self, body: &serde_json::Value) -> Result<T, AppError> {
         let url = format!("{}/search/jql", self.base_url);
-        let response = self
-            .client
-            .post(&url)
-            .header("Authorization", &self.auth_header)
-            .header("Content-Type", "application/json")
-            .json(&body)
-            .send()
-            .await
-            .map_err(JiraError::from)?;
+        self.with_retry(|| async {
+            let response = self
+                .client
+                .post(&url)
+                .header("Authorization", &self.auth_header)
+                .header("Content-Type", "application/json")
+                .json(body)
+                .send()
+                .await
+                .map_err(JiraError::from)?;
+            Self::parse_response(response).await
+        })
+        .await
+    }
+
+    /// Runs a request, retrying transient failures as the retry policy allows
+    async fn with_retry<T, F, Fut>(&self, request: F) -> Result<T, AppError>
+    where
+        F: Fn() -> Fut,
+        Fut: Future<Output = Result<T, AppError>>,
+    {
+        let mut attempt = 1;
+        loop {
+            let error = match request().await {
+                Ok(value) => return Ok(value),
+                Err(error) => error,
+            };
+
+            let Some(delay) = self.retry_policy.delay_for(attempt, &error) else {
+                return Err(error);
+            };
+            log::warn!(
+                "Jira request failed (attempt {}/{}), retrying in {:?}: {}",
+                attempt,
+                self.retry_policy.max_attempts,
+                delay,
+                error
+            );
+            self.wait_before_retry(attempt, delay).await?;
+            attempt += 1;
+        }
+    }
+
+    async fn wait_before_retry(&self, attempt: u32, delay: Duration) -> Result<(), AppError> {
+        let total_secs = delay.as_secs_f64().ceil() as u64;
+        let mut remaining = delay;
+
+        loop {
+            self.check_cancelled()?;
+            if let Some(listener) = &self.retry_listener {
+                listener(RetryWait {
+                    attempt,
+                    remaining_secs: remaining.as_secs_f64().ceil() as u64,
+                    total_secs,
+                });
+            }
+            if remaining.is_zero() {
+                return Ok(());
+            }
+
+            let step = remaining.min(Duration::from_secs(1));
+            tokio::time::sleep(step).await;
+            remaining -= step;
+        }
+    }
+
+    fn check_cancelled(&self) -> Result<(), AppError> {
+        match &self.cancel {
+            Some(cancel) => cancel.check(),
+            None => Ok(()),
+        }
+    }
 
+    async fn parse_response<T: DeserializeOwned>(
+        response: reqwest::Response,
+    ) -> Result<T, AppError> {
         let status = response.status();
 
         if status.is_success() {
-            let search_response: JiraSearchResponse = response
+            let parsed: T = response
                 .json()
                 .await
                 .map_err(|e| JiraError::ParseError(e.to_string()))?;
-            Ok(search_response)
+            Ok(parsed)
         } else if status.as_u16() == 401 {
             Err(JiraError::Unauthorized.into())
         } else if status.as_u16() == 429 {
@@ -132,6 +414,37 @@ impl JiraClient {
         }
     }
 
+    fn extract_status_transitions(
+        issue_key: &str,
+        histories: Vec<JiraChangelogHistory>,
+    ) -> Vec<StatusTransition> {
+        let mut transitions: Vec<StatusTransition> = histories
+            .into_iter()
+            .flat_map(|history| {
+                let author = history.author.map(|a| a.display_name);
+                let created = history.created;
+                history
+                    .items
+                    .into_iter()
+                    .filter(|item| item.field == "status")
+                    .filter_map(move |item| {
+                        Some(StatusTransition {
+                            jira_key: issue_key.to_string(),
+                            from_status: item.from_string,
+                            to_status: item.to_string?,
+                            author: author.clone(),
+                            transitioned_at: created.clone(),
+                        })
+                    })
+            })
+            .collect();
+
+        // Jira returns the expanded changelog newest-first but the paged endpoint oldest-first.
+        // Timestamps carry the offset of whoever made the change, so compare them parsed.
+        transitions.sort_by_cached_key(|t| parse_jira_timestamp(&t.transitioned_at));
+        transitions
+    }
+
     fn convert_issue_to_ticket(issue: crate::jira::types::JiraIssue) -> Ticket {
         Ticket {
             id: 0, // Will be set by database
@@ -151,3 +464,210 @@ impl JiraClient {
         }
     }
 }
+
+/// Combines the configured scope with the incremental `updated` clause. The scope's own
+/// ORDER BY is dropped since sync relies on a fixed ordering.
+pub fn build_search_jql(scope_jql: &str, last_sync_ts: Option<&str>) -> String {
+    let scope = strip_order_by(scope_jql);
+
+    match (scope.is_empty(), last_sync_ts) {
+        (false, Some(ts)) => format!(
+            "({}) AND updated >= \"{}\" ORDER BY updated ASC",
+            scope, ts
+        ),
+        (true, Some(ts)) => format!("updated >= \"{}\" ORDER BY updated ASC", ts),
+        (false, None) => format!("({}) ORDER BY created DESC", scope),
+        (true, None) => "ORDER BY created DESC".to_string(),
+    }
+}
+
+fn strip_order_by(jql: &str) -> &str {
+    let lower = jql.to_ascii_lowercase();
+    let bytes = lower.as_bytes();
+    let order_by = Regex::new(r"^order\s+by\b").expect("valid regex");
+    let mut quote: Option<u8> = None;
+    let mut i = 0;
+
+    while i < bytes.len() {
+        let c = bytes[i];
+        match quote {
+            Some(_) if c == b'\\' => i += 1,
+            Some(q) if c == q => quote = None,
+            Some(_) => {}
+            None if c == b'"' || c == b'\'' => quote = Some(c),
+            None => {
+                let at_word_start = i == 0 || !bytes[i - 1].is_ascii_alphanumeric();
+                if at_word_start && order_by.is_match(&lower[i..]) {
+                    return jql[..i].trim();
+                }
+            }
+        }
+        i += 1;
+    }
+
+    jql.trim()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::jira::fake_server::FakeJira;
+    use crate::jira::types::JiraChangelogItem;
+    use std::sync::atomic::{AtomicU32, Ordering};
+    use std::sync::Arc;
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap()
+    }
+
+    #[test]
+    fn test_build_search_jql_appends_incremental_clause() {
+        let jql = build_search_jql(
+            "project = SUP OR assignee = currentUser()",
+            Some("2025-01-06 10:00"),
+        );
+        assert_eq!(
+            jql,
+            "(project = SUP OR assignee = currentUser()) AND updated >= \"2025-01-06 10:00\" ORDER BY updated ASC"
+        );
+    }
+
+    #[test]
+    fn test_build_search_jql_replaces_scope_ordering() {
+        let jql = build_search_jql("project = SUP order  by priority DESC", None);
+        assert_eq!(jql, "(project = SUP) ORDER BY created DESC");
+
+        // ORDER BY inside a quoted value is not a clause
+        let jql = build_search_jql("summary ~ \"order by\"", None);
+        assert_eq!(jql, "(summary ~ \"order by\") ORDER BY created DESC");
+    }
+
+    #[test]
+    fn test_cancel_stops_retry_wait() {
+        let cancel = CancellationToken::new();
+        let listener_cancel = cancel.clone();
+        let mut client = JiraClient::new("http://localhost", "user@example.com", "token")
+            .unwrap()
+            .with_cancellation(cancel)
+            .with_retry_listener(Arc::new(move |_| listener_cancel.cancel()));
+        client.retry_policy.base_delay = Duration::from_millis(100);
+
+        let attempts = AtomicU32::new(0);
+        let result: Result<(), AppError> = runtime().block_on(client.with_retry(|| async {
+            attempts.fetch_add(1, Ordering::SeqCst);
+            Err(JiraError::ApiError {
+                status: 503,
+                body: String::new(),
+            }
+            .into())
+        }));
+
+        assert!(matches!(result, Err(AppError::SyncCancelled)));
+        assert_eq!(attempts.load(Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_verify_connection_only_asks_for_the_user() {
+        let jira = FakeJira::start(|request| match request.path.as_str() {
+            "/myself" => (200, r#"{"timeZone":"Europe/Berlin"}"#.to_string()),
+            _ => (400, "{}".to_string()),
+        });
+        let client = JiraClient::new(jira.url(), "user@example.com", "token").unwrap();
+
+        assert!(runtime().block_on(client.verify_connection()).is_ok());
+    }
+
+    fn history(created: &str, from: &str, to: &str) -> JiraChangelogHistory {
+        JiraChangelogHistory {
+            author: None,
+            created: created.to_string(),
+            items: vec![
+                JiraChangelogItem {
+                    field: "assignee".to_string(),
+                    from_string: None,
+                    to_string: Some("Alice".to_string()),
+                },
+                JiraChangelogItem {
+                    field: "status".to_string(),
+                    from_string: Some(from.to_string()),
+                    to_string: Some(to.to_string()),
+                },
+            ],
+        }
+    }
+
+    #[test]
+    fn test_extract_status_transitions_in_time_order() {
+        // Newest first, as in the expanded changelog; 09:30+0100 is before 08:45+0000
+        let histories = vec![
+            history("2025-01-06T08:45:00.000+0000", "In Progress", "Done"),
+            history("2025-01-06T09:30:00.000+0100", "Open", "In Progress"),
+        ];
+
+        let transitions = JiraClient::extract_status_transitions("TEST-1", histories);
+
+        let statuses: Vec<&str> = transitions.iter().map(|t| t.to_status.as_str()).collect();
+        assert_eq!(statuses, vec!["In Progress", "Done"]);
+        assert_eq!(transitions[0].from_status.as_deref(), Some("Open"));
+        assert_eq!(transitions[0].jira_key, "TEST-1");
+    }
+
+    #[test]
+    fn test_truncated_changelog_is_paged() {
+        let status_change = |created: &str, from: &str, to: &str| {
+            serde_json::json!({
+                "created": created,
+                "items": [{ "field": "status", "fromString": from, "toString": to }]
+            })
+        };
+        let first = status_change("2025-01-06T09:00:00.000+0000", "Open", "In Progress");
+        let second = status_change("2025-01-07T09:00:00.000+0000", "In Progress", "Review");
+        let third = status_change("2025-01-08T09:00:00.000+0000", "Review", "Done");
+
+        let jira = FakeJira::start(move |request| {
+            let response = match request.path.as_str() {
+                "/search/jql" => serde_json::json!({
+                    "issues": [{
+                        "key": "TEST-1",
+                        "fields": {
+                            "summary": "Printer jammed",
+                            "status": { "name": "Done" },
+                            "priority": { "name": "Medium" },
+                            "issuetype": { "name": "Task" },
+                            "assignee": null,
+                            "reporter": null,
+                            "created": "2025-01-06T08:00:00.000+0000",
+                            "updated": "2025-01-08T09:00:00.000+0000",
+                            "resolutiondate": "2025-01-08T09:00:00.000+0000",
+                            "labels": ["printer"],
+                            "project": { "key": "TEST" }
+                        },
+                        // Only the newest entry is expanded
+                        "changelog": { "total": 3, "histories": [third.clone()] }
+                    }]
+                }),
+                "/issue/TEST-1/changelog?startAt=0&maxResults=100" => serde_json::json!({
+                    "total": 3,
+                    "isLast": false,
+                    "values": [first.clone(), second.clone()]
+                }),
+                "/issue/TEST-1/changelog?startAt=2&maxResults=100" => serde_json::json!({
+                    "total": 3,
+                    "isLast": true,
+                    "values": [third.clone()]
+                }),
+                _ => return (404, "{}".to_string()),
+            };
+            (200, response.to_string())
+        });
+        let client = JiraClient::new(jira.url(), "user@example.com", "token").unwrap();
+
+        let page = runtime().block_on(client.fetch_ticket_page("project = TEST", None)).unwrap();
+
+        assert_eq!(page.tickets.len(), 1);
+        assert_eq!(page.tickets[0].labels, "printer");
+        assert!(page.next_page_token.is_none());
+        let statuses: Vec<&str> = page.transitions.iter().map(|t| t.to_status.as_str()).collect();
+        assert_eq!(statuses, vec!["In Progress", "Review", "Done"]);
+    }
+}
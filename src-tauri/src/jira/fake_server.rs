@@ -0,0 +1,86 @@
+use std::io::{BufRead, BufReader, Read, Write};
+use std::net::{TcpListener, TcpStream};
+use std::sync::Arc;
+use std::thread;
+
+/// A request received by `FakeJira`, with the path relative to the REST API root
+#[derive(Debug, Clone)]
+pub struct FakeRequest {
+    pub path: String,
+    pub body: String,
+}
+
+type Responder = dyn Fn(&FakeRequest) -> (u16, String) + Send + Sync;
+
+/// Minimal HTTP server standing in for Jira in tests. Every request is answered with the
+/// status and JSON body `respond` returns; a 429 also asks to retry after one second.
+pub struct FakeJira {
+    url: String,
+}
+
+impl FakeJira {
+    pub fn start<F>(respond: F) -> Self
+    where
+        F: Fn(&FakeRequest) -> (u16, String) + Send + Sync + 'static,
+    {
+        let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+        let url = format!("http://{}", listener.local_addr().unwrap());
+        let respond: Arc<Responder> = Arc::new(respond);
+
+        thread::spawn(move || {
+            for stream in listener.incoming().flatten() {
+                let respond = respond.clone();
+                thread::spawn(move || serve(stream, &*respond));
+            }
+        });
+
+        FakeJira { url }
+    }
+
+    pub fn url(&self) -> &str {
+        &self.url
+    }
+}
+
+fn serve(stream: TcpStream, respond: &Responder) {
+    let mut reader = BufReader::new(stream);
+    let mut request_line = String::new();
+    if reader.read_line(&mut request_line).is_err() {
+        return;
+    }
+    let target = request_line.split_whitespace().nth(1).unwrap_or_default();
+    let path = target.strip_prefix("/rest/api/3").unwrap_or(target).to_string();
+
+    let mut content_length = 0;
+    loop {
+        let mut header = String::new();
+        if reader.read_line(&mut header).is_err() || header.trim().is_empty() {
+            break;
+        }
+        if let Some((name, value)) = header.split_once(':') {
+            if name.eq_ignore_ascii_case("content-length") {
+                content_length = value.trim().parse().unwrap_or(0);
+            }
+        }
+    }
+    let mut body = vec![0; content_length];
+    if reader.read_exact(&mut body).is_err() {
+        return;
+    }
+
+    let (status, body) = respond(&FakeRequest {
+        path,
+        body: String::from_utf8_lossy(&body).into_owned(),
+    });
+
+    let retry_after = if status == 429 { "Retry-After: 1\r\n" } else { "" };
+    let response = format!(
+        "HTTP/1.1 {} Fake\r\nContent-Type: application/json\r\nContent-Length: {}\r\n{}\
+         Connection: close\r\n\r\n{}",
+        status,
+        body.len(),
+        retry_after,
+        body
+    );
+    let _ = reader.get_mut().write_all(response.as_bytes());
+}
@@ -1,5 +1,9 @@
 pub mod client;
+#[cfg(test)]
+pub mod fake_server;
+pub mod retry;
 pub mod types;
 
 pub use client::*;
+pub use retry::*;
 pub use types::*;
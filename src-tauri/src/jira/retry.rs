@@ -0,0 +1,131 @@
+use crate::errors::{AppError, JiraError};
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::sync::Arc;
+use std::time::Duration;
+
+/// A pause before retrying a failed Jira request, reported every second while waiting and
+/// once more with `remaining_secs` at 0 when the wait is over
+#[derive(Debug, Clone, Copy)]
+pub struct RetryWait {
+    pub attempt: u32,
+    pub remaining_secs: u64,
+    pub total_secs: u64,
+}
+
+pub type RetryListener = Arc<dyn Fn(RetryWait) + Send + Sync>;
+
+/// When and how long to wait before retrying a failed request
+#[derive(Debug, Clone)]
+pub struct RetryPolicy {
+    // Including the first attempt
+    pub max_attempts: u32,
+    pub base_delay: Duration,
+    pub max_delay: Duration,
+}
+
+impl Default for RetryPolicy {
+    fn default() -> Self {
+        RetryPolicy {
+            max_attempts: 5,
+            base_delay: Duration::from_secs(1),
+            max_delay: Duration::from_secs(60),
+        }
+    }
+}
+
+impl RetryPolicy {
+    /// Delay before the next attempt after `attempt` failed with `error`, or `None` when the
+    /// error isn't transient or the attempts are used up. Rate limits wait for `Retry-After`,
+    /// unless that is longer than `max_delay`; server and connection errors back off
+    /// exponentially with jitter.
+    pub fn delay_for(&self, attempt: u32, error: &AppError) -> Option<Duration> {
+        if attempt >= self.max_attempts {
+            return None;
+        }
+
+        match error {
+            AppError::JiraApi(JiraError::RateLimited { retry_after_secs }) => {
+                let delay = Duration::from_secs(*retry_after_secs);
+                (delay <= self.max_delay).then_some(delay)
+            }
+            AppError::JiraApi(JiraError::ApiError { status, .. }) if *status >= 500 => {
+                Some(self.backoff(attempt))
+            }
+            AppError::JiraApi(JiraError::Http(e))
+                if e.is_connect() || e.is_timeout() || e.is_request() =>
+            {
+                Some(self.backoff(attempt))
+            }
+            _ => None,
+        }
+    }
+
+    /// Exponential backoff capped at `max_delay`, randomized between half and the full delay
+    fn backoff(&self, attempt: u32) -> Duration {
+        let exponential = self
+            .base_delay
+            .saturating_mul(2u32.saturating_pow(attempt.saturating_sub(1)))
+            .min(self.max_delay);
+        let half = exponential / 2;
+        half + half.mul_f64(jitter())
+    }
+}
+
+/// Pseudo-random number in [0, 1), good enough to spread out retries
+fn jitter() -> f64 {
+    let random = RandomState::new().build_hasher().finish();
+    (random >> 11) as f64 / (1u64 << 53) as f64
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn api_error(status: u16) -> AppError {
+        JiraError::ApiError {
+            status,
+            body: String::new(),
+        }
+        .into()
+    }
+
+    #[test]
+    fn test_rate_limit_honours_retry_after() {
+        let policy = RetryPolicy::default();
+        let error: AppError = JiraError::RateLimited {
+            retry_after_secs: 42,
+        }
+        .into();
+
+        assert_eq!(policy.delay_for(1, &error), Some(Duration::from_secs(42)));
+        assert_eq!(policy.delay_for(5, &error), None);
+
+        // Waits longer than the policy allows fail right away
+        let error: AppError = JiraError::RateLimited {
+            retry_after_secs: 300,
+        }
+        .into();
+        assert_eq!(policy.delay_for(1, &error), None);
+    }
+
+    #[test]
+    fn test_server_errors_back_off_exponentially() {
+        let policy = RetryPolicy::default();
+
+        for attempt in 1..5 {
+            let delay = policy.delay_for(attempt, &api_error(503)).unwrap();
+            let full = Duration::from_secs(1 << (attempt - 1));
+            assert!(delay >= full / 2 && delay <= full, "attempt {}: {:?}", attempt, delay);
+        }
+
+        let capped = RetryPolicy {
+            max_attempts: 20,
+            ..Default::default()
+        };
+        assert!(capped.delay_for(15, &api_error(500)).unwrap() <= Duration::from_secs(60));
+
+        assert_eq!(policy.delay_for(1, &api_error(400)), None);
+        assert_eq!(policy.delay_for(1, &JiraError::Unauthorized.into()), None);
+    }
+}
@@ -7,10 +7,46 @@ pub struct JiraSearchResponse {
     pub next_page_token: Option<String>,
 }
 
+// Search response when no fields are requested
+#[derive(Deserialize)]
+pub struct JiraKeySearchResponse {
+    pub issues: Vec<JiraIssueKey>,
+    #[serde(rename = "nextPageToken")]
+    pub next_page_token: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct JiraIssueKey {
+    pub key: String,
+}
+
+#[derive(Deserialize)]
+pub struct JiraMyself {
+    #[serde(rename = "timeZone")]
+    pub time_zone: Option<String>,
+}
+
+#[derive(Deserialize)]
+pub struct JiraApproximateCount {
+    pub count: u64,
+}
+
+#[derive(Deserialize)]
+pub struct JqlParseResponse {
+    pub queries: Vec<ParsedJqlQuery>,
+}
+
+#[derive(Deserialize)]
+pub struct ParsedJqlQuery {
+    #[serde(default)]
+    pub errors: Vec<String>,
+}
+
 #[derive(Deserialize)]
 pub struct JiraIssue {
     pub key: String,
     pub fields: JiraFields,
+    pub changelog: Option<JiraChangelog>,
 }
 
 #[derive(Deserialize)]
@@ -43,3 +79,34 @@ pub struct DisplayNameField {
 pub struct KeyField {
     pub key: String,
 }
+
+#[derive(Deserialize)]
+pub struct JiraChangelog {
+    pub total: u32,
+    pub histories: Vec<JiraChangelogHistory>,
+}
+
+// Response of GET /issue/{key}/changelog, used when the expanded changelog is truncated
+#[derive(Deserialize)]
+pub struct JiraChangelogPage {
+    pub total: u32,
+    #[serde(rename = "isLast")]
+    pub is_last: Option<bool>,
+    pub values: Vec<JiraChangelogHistory>,
+}
+
+#[derive(Deserialize)]
+pub struct JiraChangelogHistory {
+    pub author: Option<DisplayNameField>,
+    pub created: String,
+    pub items: Vec<JiraChangelogItem>,
+}
+
+#[derive(Deserialize)]
+pub struct JiraChangelogItem {
+    pub field: String,
+    #[serde(rename = "fromString")]
+    pub from_string: Option<String>,
+    #[serde(rename = "toString")]
+    pub to_string: Option<String>,
+}
@@ -7,7 +7,10 @@ mod services;
 
 use commands::*;
 use db::DbPool;
+use services::scheduler::SyncScheduler;
+use services::sync_engine::SyncEngine;
 use std::path::PathBuf;
+use std::sync::Arc;
 use tauri::Manager;
 
 #[cfg_attr(mobile, tauri::mobile_entry_point)]
@@ -32,8 +35,18 @@ pub fn run() {
             let db_pool = DbPool::new(db_path_str)
                 .map_err(|e| format!("Failed to initialize database at {:?}: {}", db_path, e))?;
 
+            let sync_engine = Arc::new(SyncEngine::new(db_pool.0.clone()));
             app.manage(db_pool);
-            app.manage(SyncLock(tokio::sync::Mutex::new(false)));
+            app.manage(sync_engine);
+            app.manage(SyncScheduler::new());
+
+            // Resume the background sync saved in settings
+            let app_handle = app.handle().clone();
+            tauri::async_runtime::spawn(async move {
+                if let Err(e) = restore_scheduler(app_handle).await {
+                    log::error!("Failed to start background sync: {}", e);
+                }
+            });
 
             Ok(())
         })
@@ -44,10 +57,27 @@ pub fn run() {
             verify_jira_connection,
             save_jira_settings,
             load_jira_settings,
+            save_business_hours_settings,
+            load_business_hours_settings,
+            save_sla_policies,
+            load_sla_policies,
+            save_category_rules,
+            load_category_rules,
+            list_holidays,
+            save_holidays,
+            remove_holiday,
+            import_holidays_ics,
             trigger_sync,
+            full_resync,
+            cancel_sync,
+            start_scheduler,
+            stop_scheduler,
+            get_scheduler_status,
             get_sync_status,
+            get_sync_history,
             get_dashboard_data,
             get_all_tickets,
+            get_sla_status,
         ])
         .run(tauri::generate_context!())
         .expect("error while running tauri application");
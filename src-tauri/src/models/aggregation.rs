@@ -1,4 +1,104 @@
-use serde::Serialize;
+use chrono::{Datelike, Days, Months, NaiveDate};
+use serde::{Deserialize, Serialize};
+
+/// Date range and period size of the dashboard. The series and cumulative flow cover the
+/// periods between `from` and `to`; the other charts cover tickets created in that range.
+/// `to` defaults to today and `from` to the start of the 12th period before it.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct DashboardQuery {
+    pub from: Option<NaiveDate>,
+    pub to: Option<NaiveDate>,
+    pub granularity: Granularity,
+}
+
+#[derive(Debug, Clone, Copy, Default, PartialEq, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Granularity {
+    Day,
+    Week, // ISO week, starting on Monday
+    #[default]
+    Month,
+    Quarter,
+}
+
+/// One period of a series: `start` inclusive, `end` exclusive
+#[derive(Debug, Clone, PartialEq)]
+pub struct Period {
+    pub label: String,
+    pub start: NaiveDate,
+    pub end: NaiveDate,
+}
+
+impl Granularity {
+    /// First day of the period containing `date`
+    pub fn period_start(&self, date: NaiveDate) -> NaiveDate {
+        match self {
+            Granularity::Day => date,
+            Granularity::Week => date - Days::new(date.weekday().num_days_from_monday() as u64),
+            Granularity::Month => date.with_day(1).expect("every month has a first day"),
+            Granularity::Quarter => {
+                let month = (date.month0() / 3) * 3 + 1;
+                NaiveDate::from_ymd_opt(date.year(), month, 1).expect("valid quarter start")
+            }
+        }
+    }
+
+    /// Start of the period `count` periods after the one starting at `start`, or `None` past
+    /// the last representable date
+    pub fn add_periods(&self, start: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            Granularity::Day => start.checked_add_days(Days::new(count as u64)),
+            Granularity::Week => start.checked_add_days(Days::new(7 * count as u64)),
+            Granularity::Month => start.checked_add_months(Months::new(count)),
+            Granularity::Quarter => start.checked_add_months(Months::new(3 * count)),
+        }
+    }
+
+    /// Start of the period `count` periods before the one starting at `start`, or `None`
+    /// before the first representable date
+    pub fn sub_periods(&self, start: NaiveDate, count: u32) -> Option<NaiveDate> {
+        match self {
+            Granularity::Day => start.checked_sub_days(Days::new(count as u64)),
+            Granularity::Week => start.checked_sub_days(Days::new(7 * count as u64)),
+            Granularity::Month => start.checked_sub_months(Months::new(count)),
+            Granularity::Quarter => start.checked_sub_months(Months::new(3 * count)),
+        }
+    }
+
+    /// e.g. "2025-01-06", "2025-W02", "2025-01" or "2025-Q1"
+    pub fn label(&self, start: NaiveDate) -> String {
+        match self {
+            Granularity::Day => start.format("%Y-%m-%d").to_string(),
+            Granularity::Week => {
+                let week = start.iso_week();
+                format!("{}-W{:02}", week.year(), week.week())
+            }
+            Granularity::Month => start.format("%Y-%m").to_string(),
+            Granularity::Quarter => format!("{}-Q{}", start.year(), start.month0() / 3 + 1),
+        }
+    }
+
+    /// Every period overlapping `from..=to`, in order, or `None` when there are more than
+    /// `limit` of them
+    pub fn periods(&self, from: NaiveDate, to: NaiveDate, limit: usize) -> Option<Vec<Period>> {
+        let mut periods = Vec::new();
+        let mut start = self.period_start(from);
+        while start <= to {
+            if periods.len() == limit {
+                return None;
+            }
+            let end = self.add_periods(start, 1)?;
+            periods.push(Period {
+                label: self.label(start),
+                start,
+                end,
+            });
+            start = end;
+        }
+        Some(periods)
+    }
+}
 
 #[derive(Serialize)]
 pub struct AggregationResult {
@@ -6,7 +106,12 @@ pub struct AggregationResult {
     pub tickets_by_priority: Vec<CountEntry>,
     pub tickets_by_category: Vec<CountEntry>,
     pub tickets_over_time: Vec<TimeSeriesEntry>,
+    pub cumulative_flow: Vec<CumulativeFlowEntry>,
     pub resolution_time_by_priority: Vec<AvgEntry>,
+    pub time_in_status: Vec<StatusDurationEntry>,
+    pub cycle_time: FlowTimeStats, // first "In Progress" -> "Done"
+    pub lead_time: FlowTimeStats,  // created -> resolved
+    pub sla_compliance: Vec<SlaComplianceEntry>,
     pub summary: SummaryStats,
 }
 
@@ -18,9 +123,17 @@ pub struct CountEntry {
 
 #[derive(Serialize)]
 pub struct TimeSeriesEntry {
-    pub date: String, // "2025-01" (month) or "2025-W03" (week)
+    pub date: String, // period label, e.g. "2025-01" (month) or "2025-W03" (week)
     pub created: u32,
     pub resolved: u32,
+    pub open: u32, // still open at the end of the period
+}
+
+/// Tickets in each status at the end of a period
+#[derive(Serialize)]
+pub struct CumulativeFlowEntry {
+    pub date: String, // period label, "2025-01-06" for days
+    pub statuses: Vec<CountEntry>,
 }
 
 #[derive(Serialize)]
@@ -28,14 +141,73 @@ pub struct AvgEntry {
     pub name: String,
     pub avg_hours: f64,
     pub median_hours: f64,
+    pub business_avg_hours: f64,
+    pub business_median_hours: f64,
+    pub count: u32,
+}
+
+#[derive(Serialize)]
+pub struct StatusDurationEntry {
+    pub name: String,
+    pub avg_hours: f64,
+    pub median_hours: f64,
+    pub p90_hours: f64,
+    pub count: u32,
+}
+
+#[derive(Serialize)]
+pub struct FlowTimeStats {
+    pub avg_hours: f64,
+    pub median_hours: f64,
+    pub p90_hours: f64,
     pub count: u32,
 }
 
+#[derive(Serialize)]
+pub struct SlaComplianceEntry {
+    pub name: String, // SLA policy
+    pub first_response_met: u32,
+    pub first_response_breached: u32,
+    pub first_response_rate: Option<f64>, // None until a clock has finished or breached
+    pub resolution_met: u32,
+    pub resolution_breached: u32,
+    pub resolution_rate: Option<f64>,
+    pub at_risk: u32,
+}
+
 #[derive(Serialize)]
 pub struct SummaryStats {
-    pub total_tickets: u32,
-    pub open_tickets: u32,
-    pub resolved_tickets: u32,
+    pub total_tickets: u32, // created in the dashboard range
+    pub open_tickets: u32, // still open, whenever they were created
+    pub resolved_tickets: u32, // created in the dashboard range and resolved since
     pub avg_resolution_hours: f64,
     pub median_resolution_hours: f64,
+    pub avg_resolution_business_hours: f64,
+    pub median_resolution_business_hours: f64,
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_granularity_periods() {
+        let from = NaiveDate::from_ymd_opt(2024, 12, 31).unwrap();
+        let to = NaiveDate::from_ymd_opt(2025, 4, 1).unwrap();
+        let labels = |granularity: Granularity| -> Vec<String> {
+            granularity.periods(from, to, 100).unwrap().into_iter().map(|p| p.label).collect()
+        };
+
+        assert_eq!(labels(Granularity::Quarter), vec!["2024-Q4", "2025-Q1", "2025-Q2"]);
+        assert_eq!(labels(Granularity::Month).len(), 5);
+        assert_eq!(labels(Granularity::Week)[0], "2025-W01");
+        assert_eq!(labels(Granularity::Day).len(), 92);
+
+        let weeks = Granularity::Week.periods(from, to, 100).unwrap();
+        assert_eq!(weeks[0].start, NaiveDate::from_ymd_opt(2024, 12, 30).unwrap());
+        assert_eq!(weeks[0].end, NaiveDate::from_ymd_opt(2025, 1, 6).unwrap());
+        // Too many periods, or periods ending past the last representable date
+        assert!(Granularity::Day.periods(from, to, 91).is_none());
+        assert!(Granularity::Month.periods(NaiveDate::MAX, NaiveDate::MAX, 100).is_none());
+    }
 }
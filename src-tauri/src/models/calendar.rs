@@ -0,0 +1,8 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Holiday {
+    pub date: NaiveDate, // "2025-12-25"
+    pub name: Option<String>,
+}
@@ -1,5 +1,13 @@
 pub mod aggregation;
+pub mod calendar;
+pub mod sla;
+pub mod sync_run;
 pub mod ticket;
+pub mod transition;
 
 pub use aggregation::*;
+pub use calendar::*;
+pub use sla::*;
+pub use sync_run::*;
 pub use ticket::*;
+pub use transition::*;
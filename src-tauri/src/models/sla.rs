@@ -0,0 +1,26 @@
+use serde::Serialize;
+
+#[derive(Debug, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SlaState {
+    OnTrack,
+    AtRisk,
+    Breached,
+    Met,
+}
+
+#[derive(Debug, Serialize)]
+pub struct SlaClock {
+    pub target_hours: f64,
+    pub elapsed_hours: f64, // business hours, excluding paused statuses
+    pub remaining_hours: f64,
+    pub state: SlaState,
+}
+
+#[derive(Debug, Serialize)]
+pub struct TicketSla {
+    pub jira_key: String,
+    pub policy: String,
+    pub first_response: Option<SlaClock>,
+    pub resolution: Option<SlaClock>,
+}
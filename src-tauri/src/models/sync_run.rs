@@ -0,0 +1,53 @@
+use serde::{Deserialize, Serialize};
+
+/// What started a sync
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SyncTrigger {
+    Manual,
+    Scheduled,
+    // Re-fetches the whole scope, ignoring the watermark
+    FullResync,
+}
+
+impl SyncTrigger {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            SyncTrigger::Manual => "manual",
+            SyncTrigger::Scheduled => "scheduled",
+            SyncTrigger::FullResync => "full_resync",
+        }
+    }
+}
+
+/// One recorded sync. `status` is running, succeeded, failed or cancelled.
+#[derive(Debug, Clone, Serialize)]
+pub struct SyncRun {
+    pub id: i64,
+    pub trigger: String,
+    pub status: String,
+    pub started_at: String,
+    pub finished_at: Option<String>,
+    pub duration_ms: Option<i64>,
+    pub fetched: i64,
+    pub inserted: i64,
+    pub updated: i64,
+    pub unchanged: i64,
+    pub out_of_scope: i64,
+    pub error: Option<String>,
+}
+
+/// Progress of an unfinished sync of one scope, so the next sync can resume after the pages
+/// already saved
+#[derive(Debug, Clone)]
+pub struct SyncCheckpoint {
+    pub scope_jql: String,
+    // The exact search being paged through, including its `updated` clause
+    pub jql: String,
+    pub next_page_token: Option<String>,
+    pub pages: i64,
+    pub tickets: i64,
+    // Newest `updated` seen so far, becomes the scope's watermark when the sync completes
+    pub max_updated: Option<String>,
+    pub started_at: String,
+}
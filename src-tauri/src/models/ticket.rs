@@ -1,4 +1,5 @@
 use serde::{Deserialize, Serialize};
+use std::collections::BTreeSet;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct Ticket {
@@ -17,3 +18,25 @@ pub struct Ticket {
     pub project_key: String,
     pub category: Option<String>, // computed locally
 }
+
+/// Values a ticket field must (`include`) or must not (`exclude`) have. An empty `include`
+/// allows any value.
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct FilterSet {
+    pub include: BTreeSet<String>,
+    pub exclude: BTreeSet<String>,
+}
+
+/// Restricts the tickets the dashboard and ticket list show; every field must match. Tickets
+/// without an assignee or category match "Unassigned" and "Uncategorized".
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct TicketFilter {
+    pub projects: FilterSet,
+    pub assignees: FilterSet,
+    pub issue_types: FilterSet,
+    pub labels: FilterSet, // matches tickets with any of the labels
+    pub categories: FilterSet,
+    pub statuses: FilterSet,
+}
@@ -0,0 +1,10 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct StatusTransition {
+    pub jira_key: String,
+    pub from_status: Option<String>, // None if Jira didn't record a previous status
+    pub to_status: String,
+    pub author: Option<String>,
+    pub transitioned_at: String, // ISO 8601
+}
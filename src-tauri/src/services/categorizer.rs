@@ -1,8 +1,8 @@
 use crate::models::Ticket;
 use regex::Regex;
-use serde::Deserialize;
+use serde::{Deserialize, Serialize};
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct CategoryRule {
     pub id: String,
     pub name: String,
@@ -12,7 +12,7 @@ pub struct CategoryRule {
     pub match_mode: MatchMode,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct RuleCondition {
     pub field: String,
     pub operator: String,
@@ -21,7 +21,7 @@ pub struct RuleCondition {
     pub case_sensitive: bool,
 }
 
-#[derive(Debug, Clone, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(rename_all = "lowercase")]
 pub enum MatchMode {
     All,
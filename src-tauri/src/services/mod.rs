@@ -1,6 +1,10 @@
+pub mod cancellation;
 pub mod categorizer;
 pub mod scheduler;
+pub mod sla;
+pub mod sync_engine;
 pub mod time_calc;
 
+pub use cancellation::*;
 pub use categorizer::*;
 pub use time_calc::*;
@@ -1,159 +1,426 @@
-use crate::db::DbPool;
 use crate::errors::AppError;
-use std::sync::Arc;
-use tauri::Emitter;
-use tokio::sync::Mutex as TokioMutex;
-use tokio::time::{interval, Duration};
+use crate::services::time_calc::{resolve_local, BusinessHoursSettings, WorkCalendar, WorkSchedule};
+use chrono::{DateTime, Local, NaiveDateTime, NaiveTime, TimeZone, Utc};
+use chrono_tz::Tz;
+use serde::{Deserialize, Serialize};
+use std::future::Future;
+use std::str::FromStr;
+use std::sync::{Arc, Mutex};
+use tokio::time::Duration;
 
+// How often the scheduler compares the wall clock with the next run. Monotonic timers stop
+// while the machine sleeps, so a single long sleep would oversleep after a wake-up.
+const CLOCK_CHECK_SECS: u64 = 30;
+
+/// When background syncs run
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "kind", rename_all = "snake_case")]
+pub enum SyncSchedule {
+    /// Every `minutes`; 0 disables background sync
+    Interval { minutes: u64 },
+    /// Runs whenever any of the expressions fires, e.g. `["*/10 8-17 * * Mon-Fri", "0 * * * *"]`.
+    /// Five-field expressions get a leading seconds field. Prefer day names over numbers:
+    /// numeric days of week start at 1 = Sunday.
+    Cron { expressions: Vec<String> },
+    /// Every `work_minutes` during the business-hours work schedule, every
+    /// `off_hours_minutes` outside it, or not at all outside it when `None`
+    WorkingHours {
+        work_minutes: u64,
+        off_hours_minutes: Option<u64>,
+    },
+}
+
+/// Persisted background sync configuration, restored on launch
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SchedulerSettings {
+    pub enabled: bool,
+    pub interval_minutes: u64,
+    // Takes precedence over `interval_minutes` when set
+    #[serde(default)]
+    pub schedule: Option<SyncSchedule>,
+}
+
+impl SchedulerSettings {
+    pub fn sync_schedule(&self) -> SyncSchedule {
+        self.schedule.clone().unwrap_or(SyncSchedule::Interval {
+            minutes: self.interval_minutes,
+        })
+    }
+}
+
+impl Default for SchedulerSettings {
+    fn default() -> Self {
+        SchedulerSettings {
+            enabled: false,
+            interval_minutes: 30,
+            schedule: None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct SchedulerStatus {
+    pub running: bool,
+    pub schedule: Option<SyncSchedule>,
+    pub next_run_at: Option<String>,
+}
+
+/// A validated schedule, resolved against the team's work schedule, holidays and timezone
+pub struct SyncPlan {
+    schedule: SyncSchedule,
+    cron: Vec<cron::Schedule>,
+    work_schedule: WorkSchedule,
+    calendar: WorkCalendar,
+    timezone: Option<Tz>,
+}
+
+impl SyncPlan {
+    pub fn new(
+        schedule: SyncSchedule,
+        business_hours: &BusinessHoursSettings,
+        calendar: WorkCalendar,
+    ) -> Result<Self, AppError> {
+        let cron = match &schedule {
+            SyncSchedule::Cron { expressions } if expressions.is_empty() => {
+                return Err(AppError::Config("No cron expression given".to_string()));
+            }
+            SyncSchedule::Cron { expressions } => expressions
+                .iter()
+                .map(|expression| parse_cron(expression))
+                .collect::<Result<Vec<_>, _>>()?,
+            SyncSchedule::WorkingHours { work_minutes: 0, .. }
+            | SyncSchedule::WorkingHours {
+                off_hours_minutes: Some(0),
+                ..
+            } => {
+                return Err(AppError::Config(
+                    "Sync interval must be at least 1 minute".to_string(),
+                ));
+            }
+            _ => Vec::new(),
+        };
+
+        Ok(SyncPlan {
+            schedule,
+            cron,
+            work_schedule: business_hours.work_schedule()?,
+            calendar,
+            timezone: business_hours.team_timezone()?,
+        })
+    }
+
+    pub fn is_disabled(&self) -> bool {
+        self.schedule == SyncSchedule::Interval { minutes: 0 }
+    }
+
+    /// Next run strictly after `now`, in the team's timezone or the system one
+    pub fn next_run_after(&self, now: DateTime<Utc>) -> Option<DateTime<Utc>> {
+        match self.timezone {
+            Some(tz) => self.next_run_in(now.with_timezone(&tz)),
+            None => self.next_run_in(now.with_timezone(&Local)),
+        }
+    }
+
+    fn next_run_in<T: TimeZone>(&self, now: DateTime<T>) -> Option<DateTime<Utc>> {
+        let next = match &self.schedule {
+            SyncSchedule::Interval { minutes: 0 } => return None,
+            SyncSchedule::Interval { minutes } => now + minutes_duration(*minutes),
+            SyncSchedule::Cron { .. } => self
+                .cron
+                .iter()
+                .filter_map(|schedule| schedule.after(&now).next())
+                .min()?,
+            SyncSchedule::WorkingHours {
+                work_minutes,
+                off_hours_minutes,
+            } => {
+                let local = now.naive_local();
+                if self.is_working_time(local) {
+                    now + minutes_duration(*work_minutes)
+                } else {
+                    // Outside working hours, but never later than the start of the next shift
+                    let work_start = self
+                        .next_work_start(local)
+                        .map(|start| resolve_local(&now.timezone(), start));
+                    let off_hours = off_hours_minutes
+                        .map(|minutes| now.clone() + minutes_duration(minutes));
+                    match (work_start, off_hours) {
+                        (Some(start), Some(off_hours)) => start.min(off_hours),
+                        (start, off_hours) => start.or(off_hours)?,
+                    }
+                }
+            }
+        };
+
+        Some(next.with_timezone(&Utc))
+    }
+
+    fn is_working_time(&self, local: NaiveDateTime) -> bool {
+        self.work_schedule.is_working_time(local)
+            && (self.work_schedule.always_on || !self.calendar.is_holiday(local.date()))
+    }
+
+    /// Start of the first shift after `local` that doesn't fall on a holiday
+    fn next_work_start(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        let mut from = local;
+        loop {
+            let start = self.work_schedule.next_work_start(from)?;
+            if !self.calendar.is_holiday(start.date()) {
+                return Some(start);
+            }
+            // Look again from the last moment of the holiday
+            let next_day = start.date().succ_opt()?.and_time(NaiveTime::MIN);
+            from = next_day - chrono::Duration::nanoseconds(1);
+        }
+    }
+}
+
+fn parse_cron(expression: &str) -> Result<cron::Schedule, AppError> {
+    let expression = expression.trim();
+    // The cron crate expects seconds first; accept the classic five-field form too
+    let normalized = if expression.split_whitespace().count() == 5 {
+        format!("0 {}", expression)
+    } else {
+        expression.to_string()
+    };
+
+    cron::Schedule::from_str(&normalized)
+        .map_err(|e| AppError::Config(format!("Invalid cron expression '{}': {}", expression, e)))
+}
+
+fn minutes_duration(minutes: u64) -> chrono::Duration {
+    chrono::Duration::minutes(minutes as i64)
+}
+
+struct RunningSchedule {
+    handle: tauri::async_runtime::JoinHandle<()>,
+    schedule: SyncSchedule,
+    next_run_at: Arc<Mutex<Option<DateTime<Utc>>>>,
+}
+
+/// Runs a background job on a schedule. Starting again replaces the running schedule;
+/// stopping aborts the spawned task.
+#[derive(Default)]
 pub struct SyncScheduler {
-    interval_minutes: u64,
-    is_running: Arc<TokioMutex<bool>>,
+    running: Mutex<Option<RunningSchedule>>,
 }
 
 impl SyncScheduler {
-    pub fn new(interval_minutes: u64) -> Self {
-        SyncScheduler {
-            interval_minutes,
-            is_running: Arc::new(TokioMutex::new(false)),
-        }
+    pub fn new() -> Self {
+        Self::default()
     }
 
-    pub async fn start(
-        &self,
-        db_pool: Arc<std::sync::Mutex<rusqlite::Connection>>,
-        jira_url: String,
-        email: String,
-        category_rules_json: String,
-        app_handle: tauri::AppHandle,
-    ) {
-        if self.interval_minutes == 0 {
+    /// Runs `job` at every run of `plan`. Runs missed while the machine was asleep are skipped
+    /// in favour of a single catch-up run on wake.
+    pub fn start<F, Fut>(&self, plan: SyncPlan, job: F)
+    where
+        F: Fn() -> Fut + Send + Sync + 'static,
+        Fut: Future<Output = ()> + Send + 'static,
+    {
+        self.stop();
+
+        if plan.is_disabled() {
             log::info!("Background sync disabled (interval = 0)");
             return;
         }
 
-        let mut is_running = self.is_running.lock().await;
-        if *is_running {
-            log::warn!("Background sync already running");
-            return;
-        }
-        *is_running = true;
-        drop(is_running);
+        let schedule = plan.schedule.clone();
+        let next_run_at = Arc::new(Mutex::new(None));
+        let next_run_clone = next_run_at.clone();
 
-        let interval_minutes = self.interval_minutes;
+        let handle = tauri::async_runtime::spawn(async move {
+            loop {
+                let Some(next_run) = plan.next_run_after(Utc::now()) else {
+                    log::warn!("Background sync schedule has no upcoming runs");
+                    break;
+                };
+                if let Ok(mut next) = next_run_clone.lock() {
+                    *next = Some(next_run);
+                }
 
-        tokio::spawn(async move {
-            let mut ticker = interval(Duration::from_secs(interval_minutes * 60));
+                while Utc::now() < next_run {
+                    let remaining = (next_run - Utc::now()).to_std().unwrap_or_default();
+                    tokio::time::sleep(remaining.min(Duration::from_secs(CLOCK_CHECK_SECS)))
+                        .await;
+                }
 
-            loop {
-                ticker.tick().await;
-                log::info!("Background sync triggered");
-
-                // Emit event
-                app_handle.emit("background-sync-started", ()).ok();
-
-                // Perform sync (call the sync logic without the lock check)
-                match perform_background_sync(&db_pool, &jira_url, &email, &category_rules_json).await {
-                    Ok(count) => {
-                        log::info!("Background sync completed: {} tickets", count);
-                        app_handle
-                            .emit("background-sync-complete", count)
-                            .ok();
-                    }
-                    Err(e) => {
-                        log::error!("Background sync failed: {}", e);
-                        app_handle
-                            .emit("background-sync-error", e.to_string())
-                            .ok();
-                    }
+                if is_catch_up(next_run, Utc::now()) {
+                    log::info!("Missed background sync at {}, catching up", next_run);
+                } else {
+                    log::info!("Background sync triggered");
                 }
+                job().await;
             }
         });
+
+        if let Ok(mut running) = self.running.lock() {
+            *running = Some(RunningSchedule {
+                handle,
+                schedule: schedule.clone(),
+                next_run_at,
+            });
+        }
+        log::info!("Background sync scheduled: {:?}", schedule);
     }
 
-    pub async fn stop(&self) {
-        let mut is_running = self.is_running.lock().await;
-        *is_running = false;
+    /// Aborts the scheduled task, including a sync it is running, which then records itself as
+    /// cancelled. Returns false when nothing was scheduled.
+    pub fn stop(&self) -> bool {
+        let Some(schedule) = self.running.lock().ok().and_then(|mut running| running.take())
+        else {
+            return false;
+        };
+
+        schedule.handle.abort();
         log::info!("Background sync stopped");
+        true
     }
-}
 
-async fn perform_background_sync(
-    db_pool: &Arc<std::sync::Mutex<rusqlite::Connection>>,
-    jira_url: &str,
-    email: &str,
-    category_rules_json: &str,
-) -> Result<usize, AppError> {
-    // Get token
-    let token = crate::commands::settings::get_jira_token().await?;
-
-    // Parse category rules
-    let rules_wrapper: crate::commands::sync::CategoryRulesWrapper =
-        serde_json::from_str(category_rules_json)
-            .map_err(|e| AppError::Config(format!("Failed to parse category rules: {}", e)))?;
-    let category_rules = rules_wrapper.category_rules;
-
-    // Create Jira client
-    let client = crate::jira::JiraClient::new(jira_url, email, &token)?;
-
-    // Get last sync timestamp
-    let db_clone = db_pool.clone();
-    let last_sync_ts = tauri::async_runtime::spawn_blocking(move || {
-        let conn = db_clone
-            .lock()
-            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-        crate::db::get_sync_metadata(&conn, "last_sync_at")
-    })
-    .await
-    .map_err(|_| AppError::Internal("Task join failed".to_string()))??;
-
-    // Fetch tickets from Jira
-    let mut tickets = client.fetch_tickets(last_sync_ts.as_deref()).await?;
-
-    // Categorize tickets
-    for ticket in &mut tickets {
-        ticket.category = crate::services::categorize_ticket(ticket, &category_rules);
-    }
-
-    let synced_count = tickets.len();
-
-    // Store in database
-    let db_clone = db_pool.clone();
-    tauri::async_runtime::spawn_blocking(move || {
-        let conn = db_clone
-            .lock()
-            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
-
-        for ticket in &tickets {
-            crate::db::upsert_ticket(&conn, ticket)?;
+    pub fn status(&self) -> SchedulerStatus {
+        let running = self.running.lock().ok();
+        match running.as_ref().and_then(|running| running.as_ref()) {
+            Some(schedule) => SchedulerStatus {
+                running: true,
+                schedule: Some(schedule.schedule.clone()),
+                next_run_at: schedule
+                    .next_run_at
+                    .lock()
+                    .ok()
+                    .and_then(|next_run| *next_run)
+                    .map(|next_run| next_run.to_rfc3339()),
+            },
+            None => SchedulerStatus {
+                running: false,
+                schedule: None,
+                next_run_at: None,
+            },
         }
+    }
+}
 
-        // Update last sync timestamp
-        let now = chrono::Utc::now().to_rfc3339();
-        crate::db::set_sync_metadata(&conn, "last_sync_at", &now)?;
-
-        Ok::<(), AppError>(())
-    })
-    .await
-    .map_err(|_| AppError::Internal("Task join failed".to_string()))??;
-
-    Ok(synced_count)
+/// A run noticed well after it was due means the machine was asleep
+fn is_catch_up(scheduled: DateTime<Utc>, now: DateTime<Utc>) -> bool {
+    now - scheduled > chrono::Duration::seconds(2 * CLOCK_CHECK_SECS as i64)
 }
 
 #[cfg(test)]
 mod tests {
     use super::*;
+    use crate::models::Holiday;
+
+    fn utc(value: &str) -> DateTime<Utc> {
+        DateTime::parse_from_rfc3339(value).unwrap().with_timezone(&Utc)
+    }
+
+    fn plan(schedule: SyncSchedule) -> SyncPlan {
+        plan_with_holidays(schedule, &[])
+    }
+
+    fn plan_with_holidays(schedule: SyncSchedule, holidays: &[&str]) -> SyncPlan {
+        let business_hours = BusinessHoursSettings {
+            work_start_hour: 8,
+            work_end_hour: 18,
+            timezone: Some("Europe/Berlin".to_string()),
+            ..Default::default()
+        };
+        let holidays: Vec<Holiday> = holidays
+            .iter()
+            .map(|date| Holiday {
+                date: date.parse().unwrap(),
+                name: None,
+            })
+            .collect();
+        SyncPlan::new(schedule, &business_hours, WorkCalendar::new(&holidays)).unwrap()
+    }
 
     #[test]
     fn test_interval_calculation() {
-        let scheduler = SyncScheduler::new(30);
-        assert_eq!(scheduler.interval_minutes, 30);
-        // 30 minutes * 60 seconds = 1800 seconds
-        assert_eq!(scheduler.interval_minutes * 60, 1800);
+        let plan = plan(SyncSchedule::Interval { minutes: 30 });
+        let next = plan.next_run_after(utc("2025-01-06T09:45:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-06T10:15:00Z"));
     }
 
     #[test]
     fn test_disabled_scheduler() {
-        let scheduler = SyncScheduler::new(0);
-        assert_eq!(scheduler.interval_minutes, 0);
+        let scheduler = SyncScheduler::new();
+        scheduler.start(plan(SyncSchedule::Interval { minutes: 0 }), || async {});
+
+        let status = scheduler.status();
+        assert!(!status.running);
+        assert_eq!(status.next_run_at, None);
+        assert!(!scheduler.stop());
+    }
+
+    #[test]
+    fn test_cron_expressions_combine() {
+        let plan = plan(SyncSchedule::Cron {
+            expressions: vec!["*/10 8-17 * * Mon-Fri".to_string(), "0 * * * *".to_string()],
+        });
+
+        // Monday 09:03 in Berlin: the weekday rule fires first
+        let next = plan.next_run_after(utc("2025-01-06T08:03:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-06T08:10:00Z"));
+
+        // Saturday 09:03 in Berlin: only the hourly rule applies
+        let next = plan.next_run_after(utc("2025-01-11T08:03:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-11T09:00:00Z"));
+
+        let invalid = SyncPlan::new(
+            SyncSchedule::Cron {
+                expressions: vec!["every ten minutes".to_string()],
+            },
+            &BusinessHoursSettings::default(),
+            WorkCalendar::default(),
+        );
+        assert!(invalid.is_err());
+    }
+
+    #[test]
+    fn test_working_hours_schedule() {
+        let quiet_nights = plan(SyncSchedule::WorkingHours {
+            work_minutes: 10,
+            off_hours_minutes: None,
+        });
+        // Monday 10:00 in Berlin
+        let next = quiet_nights.next_run_after(utc("2025-01-06T09:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-06T09:10:00Z"));
+        // Friday 20:00 in Berlin: nothing until Monday 08:00
+        let next = quiet_nights.next_run_after(utc("2025-01-10T19:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-13T07:00:00Z"));
+
+        let hourly_nights = plan(SyncSchedule::WorkingHours {
+            work_minutes: 10,
+            off_hours_minutes: Some(60),
+        });
+        let next = hourly_nights.next_run_after(utc("2025-01-10T19:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-10T20:00:00Z"));
+        // Monday 07:30 in Berlin: the shift starts before the next hourly run
+        let next = hourly_nights.next_run_after(utc("2025-01-13T06:30:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-13T07:00:00Z"));
+    }
+
+    #[test]
+    fn test_working_hours_skip_holidays() {
+        // Monday 13 and Tuesday 14 January are holidays
+        let plan = plan_with_holidays(
+            SyncSchedule::WorkingHours {
+                work_minutes: 10,
+                off_hours_minutes: None,
+            },
+            &["2025-01-13", "2025-01-14"],
+        );
+        // Friday 20:00 in Berlin: the next shift is Wednesday 08:00
+        let next = plan.next_run_after(utc("2025-01-10T19:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-15T07:00:00Z"));
+        // Monday 10:00 in Berlin is within the usual hours, but a holiday
+        let next = plan.next_run_after(utc("2025-01-13T09:00:00Z")).unwrap();
+        assert_eq!(next, utc("2025-01-15T07:00:00Z"));
+    }
+
+    #[test]
+    fn test_catch_up_after_sleep() {
+        let scheduled = utc("2025-01-06T09:00:00Z");
+        assert!(!is_catch_up(scheduled, utc("2025-01-06T09:00:20Z")));
+        assert!(is_catch_up(scheduled, utc("2025-01-06T11:30:00Z")));
     }
 }
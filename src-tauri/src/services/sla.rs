@@ -0,0 +1,389 @@
+use crate::errors::AppError;
+use crate::models::{SlaClock, SlaComplianceEntry, SlaState, StatusTransition, Ticket, TicketSla};
+use crate::services::time_calc::{
+    business_hours_between_instants, parse_jira_timestamp, BusinessHoursSettings, WorkCalendar,
+};
+use chrono::{DateTime, FixedOffset};
+use serde::{Deserialize, Serialize};
+use std::collections::{HashMap, HashSet};
+
+fn default_at_risk_ratio() -> f64 {
+    0.8
+}
+
+/// SLA targets for the tickets matching a priority and issue type. Targets are in business hours.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SlaPolicy {
+    pub name: String,
+    // Empty means "any"
+    #[serde(default)]
+    pub priorities: Vec<String>,
+    #[serde(default)]
+    pub issue_types: Vec<String>,
+    pub first_response_hours: Option<f64>,
+    pub resolution_hours: Option<f64>,
+    // Statuses that stop the clock, e.g. "Waiting for Customer"
+    #[serde(default)]
+    pub pause_statuses: Vec<String>,
+    // Share of the target after which a running clock counts as at risk
+    #[serde(default = "default_at_risk_ratio")]
+    pub at_risk_ratio: f64,
+}
+
+impl SlaPolicy {
+    pub fn validate(&self) -> Result<(), AppError> {
+        let targets = [
+            ("first response", self.first_response_hours),
+            ("resolution", self.resolution_hours),
+        ];
+        for (clock, hours) in targets {
+            if hours.is_some_and(|hours| !(hours > 0.0 && hours.is_finite())) {
+                return Err(AppError::Config(format!(
+                    "SLA policy '{}': {} target must be a positive number of hours",
+                    self.name, clock
+                )));
+            }
+        }
+        if !(0.0..=1.0).contains(&self.at_risk_ratio) {
+            return Err(AppError::Config(format!(
+                "SLA policy '{}': at-risk ratio must be between 0 and 1",
+                self.name
+            )));
+        }
+        Ok(())
+    }
+
+    fn applies_to(&self, ticket: &Ticket) -> bool {
+        (self.priorities.is_empty() || self.priorities.contains(&ticket.priority))
+            && (self.issue_types.is_empty() || self.issue_types.contains(&ticket.issue_type))
+    }
+}
+
+/// Checks every policy, and that names are unique since results are grouped by name
+pub fn validate_policies(policies: &[SlaPolicy]) -> Result<(), AppError> {
+    let mut names = HashSet::new();
+    for policy in policies {
+        policy.validate()?;
+        if !names.insert(policy.name.as_str()) {
+            return Err(AppError::Config(format!(
+                "Duplicate SLA policy name: {}",
+                policy.name
+            )));
+        }
+    }
+    Ok(())
+}
+
+/// First policy matching the ticket, in the order the user defined them
+pub fn find_policy<'a>(ticket: &Ticket, policies: &'a [SlaPolicy]) -> Option<&'a SlaPolicy> {
+    policies.iter().find(|policy| policy.applies_to(ticket))
+}
+
+/// Computes both SLA clocks of a ticket. First response is the first status change after
+/// creation. Time spent in a pause status doesn't count towards either clock.
+pub fn evaluate_ticket(
+    ticket: &Ticket,
+    transitions: &[StatusTransition],
+    policy: &SlaPolicy,
+    business_hours: &BusinessHoursSettings,
+    calendar: &WorkCalendar,
+    now: DateTime<FixedOffset>,
+) -> Result<Option<TicketSla>, AppError> {
+    let Some(created_at) = parse_jira_timestamp(&ticket.created_at) else {
+        return Ok(None);
+    };
+    let resolved_at = ticket.resolved_at.as_deref().and_then(parse_jira_timestamp);
+
+    let mut transitions: Vec<(DateTime<FixedOffset>, &StatusTransition)> = transitions
+        .iter()
+        .filter_map(|t| parse_jira_timestamp(&t.transitioned_at).map(|at| (at, t)))
+        .collect();
+    transitions.sort_by_key(|(at, _)| *at);
+
+    // Status periods as (status, entered_at), starting with the status the ticket was created in
+    let initial_status = transitions
+        .first()
+        .and_then(|(_, t)| t.from_status.clone())
+        .unwrap_or_else(|| ticket.status.clone());
+    let mut periods = vec![(initial_status, created_at)];
+    periods.extend(transitions.iter().map(|(at, t)| (t.to_status.clone(), *at)));
+
+    let clock_context = ClockContext {
+        periods: &periods,
+        policy,
+        business_hours,
+        calendar,
+        priority: &ticket.priority,
+    };
+
+    let first_response_at = transitions.first().map(|(at, _)| *at).or(resolved_at);
+    let first_response = policy
+        .first_response_hours
+        .map(|target| clock_context.clock(target, created_at, first_response_at, now))
+        .transpose()?;
+    let resolution = policy
+        .resolution_hours
+        .map(|target| clock_context.clock(target, created_at, resolved_at, now))
+        .transpose()?;
+
+    Ok(Some(TicketSla {
+        jira_key: ticket.jira_key.clone(),
+        policy: policy.name.clone(),
+        first_response,
+        resolution,
+    }))
+}
+
+struct ClockContext<'a> {
+    periods: &'a [(String, DateTime<FixedOffset>)],
+    policy: &'a SlaPolicy,
+    business_hours: &'a BusinessHoursSettings,
+    calendar: &'a WorkCalendar,
+    priority: &'a str,
+}
+
+impl ClockContext<'_> {
+    fn clock(
+        &self,
+        target_hours: f64,
+        started_at: DateTime<FixedOffset>,
+        stopped_at: Option<DateTime<FixedOffset>>,
+        now: DateTime<FixedOffset>,
+    ) -> Result<SlaClock, AppError> {
+        let until = stopped_at.unwrap_or(now);
+        let schedule = self.business_hours.schedule_for(self.priority)?;
+        let team_tz = self.business_hours.team_timezone()?;
+
+        let mut elapsed_hours = 0.0;
+        for (idx, (status, entered_at)) in self.periods.iter().enumerate() {
+            if self.policy.pause_statuses.contains(status) {
+                continue;
+            }
+
+            let period_end = self.periods.get(idx + 1).map(|(_, at)| *at).unwrap_or(until);
+            let from = (*entered_at).max(started_at);
+            let to = period_end.min(until);
+            if to > from {
+                elapsed_hours +=
+                    business_hours_between_instants(from, to, team_tz, &schedule, self.calendar)?;
+            }
+        }
+
+        let state = match stopped_at {
+            Some(_) if elapsed_hours <= target_hours => SlaState::Met,
+            Some(_) => SlaState::Breached,
+            None if elapsed_hours > target_hours => SlaState::Breached,
+            None if elapsed_hours >= target_hours * self.policy.at_risk_ratio => SlaState::AtRisk,
+            None => SlaState::OnTrack,
+        };
+
+        Ok(SlaClock {
+            target_hours,
+            elapsed_hours,
+            remaining_hours: (target_hours - elapsed_hours).max(0.0),
+            state,
+        })
+    }
+}
+
+/// Evaluates every ticket that has a matching policy
+pub fn evaluate_tickets(
+    tickets: &[Ticket],
+    transitions: &HashMap<String, Vec<StatusTransition>>,
+    policies: &[SlaPolicy],
+    business_hours: &BusinessHoursSettings,
+    calendar: &WorkCalendar,
+    now: DateTime<FixedOffset>,
+) -> Result<Vec<TicketSla>, AppError> {
+    let mut results = Vec::new();
+
+    for ticket in tickets {
+        let Some(policy) = find_policy(ticket, policies) else {
+            continue;
+        };
+        let ticket_transitions = transitions
+            .get(&ticket.jira_key)
+            .map(Vec::as_slice)
+            .unwrap_or_default();
+
+        if let Some(sla) =
+            evaluate_ticket(ticket, ticket_transitions, policy, business_hours, calendar, now)?
+        {
+            results.push(sla);
+        }
+    }
+
+    Ok(results)
+}
+
+/// Compliance per policy. Rates are met / (met + breached); running clocks that haven't
+/// breached yet are left out of the rate and counted as at risk or on track.
+pub fn compliance_by_policy(
+    results: &[TicketSla],
+    policies: &[SlaPolicy],
+) -> Vec<SlaComplianceEntry> {
+    policies
+        .iter()
+        .map(|policy| {
+            let mut entry = SlaComplianceEntry {
+                name: policy.name.clone(),
+                first_response_met: 0,
+                first_response_breached: 0,
+                first_response_rate: None,
+                resolution_met: 0,
+                resolution_breached: 0,
+                resolution_rate: None,
+                at_risk: 0,
+            };
+
+            for result in results.iter().filter(|r| r.policy == policy.name) {
+                let clocks = [
+                    (&result.first_response, true),
+                    (&result.resolution, false),
+                ];
+                for (clock, is_first_response) in clocks {
+                    let Some(clock) = clock else {
+                        continue;
+                    };
+                    match (clock.state, is_first_response) {
+                        (SlaState::Met, true) => entry.first_response_met += 1,
+                        (SlaState::Breached, true) => entry.first_response_breached += 1,
+                        (SlaState::Met, false) => entry.resolution_met += 1,
+                        (SlaState::Breached, false) => entry.resolution_breached += 1,
+                        (SlaState::AtRisk, _) => entry.at_risk += 1,
+                        (SlaState::OnTrack, _) => {}
+                    }
+                }
+            }
+
+            entry.first_response_rate =
+                compliance_rate(entry.first_response_met, entry.first_response_breached);
+            entry.resolution_rate =
+                compliance_rate(entry.resolution_met, entry.resolution_breached);
+            entry
+        })
+        .collect()
+}
+
+fn compliance_rate(met: u32, breached: u32) -> Option<f64> {
+    let total = met + breached;
+    (total > 0).then(|| met as f64 / total as f64)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn ticket(created_at: &str, resolved_at: Option<&str>) -> Ticket {
+        Ticket {
+            id: 1,
+            jira_key: "SUP-1".to_string(),
+            summary: "Printer on fire".to_string(),
+            status: "Done".to_string(),
+            priority: "High".to_string(),
+            issue_type: "Incident".to_string(),
+            assignee: None,
+            reporter: None,
+            created_at: created_at.to_string(),
+            updated_at: created_at.to_string(),
+            resolved_at: resolved_at.map(str::to_string),
+            labels: String::new(),
+            project_key: "SUP".to_string(),
+            category: None,
+        }
+    }
+
+    fn transition(from: &str, to: &str, at: &str) -> StatusTransition {
+        StatusTransition {
+            jira_key: "SUP-1".to_string(),
+            from_status: Some(from.to_string()),
+            to_status: to.to_string(),
+            author: None,
+            transitioned_at: at.to_string(),
+        }
+    }
+
+    fn policy() -> SlaPolicy {
+        SlaPolicy {
+            name: "High incidents".to_string(),
+            priorities: vec!["High".to_string()],
+            issue_types: Vec::new(),
+            first_response_hours: Some(2.0),
+            resolution_hours: Some(8.0),
+            pause_statuses: vec!["Waiting for Customer".to_string()],
+            at_risk_ratio: 0.8,
+        }
+    }
+
+    #[test]
+    fn test_pause_status_stops_the_clock() {
+        // Monday 09:00 -> In Progress 10:00 -> waiting 11:00-15:00 -> Done 17:00
+        let ticket = ticket("2025-01-06T09:00:00Z", Some("2025-01-06T17:00:00Z"));
+        let transitions = [
+            transition("Open", "In Progress", "2025-01-06T10:00:00Z"),
+            transition("In Progress", "Waiting for Customer", "2025-01-06T11:00:00Z"),
+            transition("Waiting for Customer", "In Progress", "2025-01-06T15:00:00Z"),
+            transition("In Progress", "Done", "2025-01-06T17:00:00Z"),
+        ];
+        let now = parse_jira_timestamp("2025-01-10T00:00:00Z").unwrap();
+
+        let sla = evaluate_ticket(
+            &ticket,
+            &transitions,
+            &policy(),
+            &BusinessHoursSettings::default(),
+            &WorkCalendar::default(),
+            now,
+        )
+        .unwrap()
+        .unwrap();
+
+        let first_response = sla.first_response.unwrap();
+        assert_eq!(first_response.elapsed_hours, 1.0);
+        assert_eq!(first_response.state, SlaState::Met);
+
+        let resolution = sla.resolution.unwrap();
+        assert_eq!(resolution.elapsed_hours, 4.0);
+        assert_eq!(resolution.remaining_hours, 4.0);
+        assert_eq!(resolution.state, SlaState::Met);
+    }
+
+    #[test]
+    fn test_running_clock_at_risk_and_breached() {
+        let open = ticket("2025-01-06T09:00:00Z", None);
+        let settings = BusinessHoursSettings::default();
+        let calendar = WorkCalendar::default();
+
+        // 7 of 8 hours used
+        let now = parse_jira_timestamp("2025-01-06T16:00:00Z").unwrap();
+        let sla = evaluate_ticket(&open, &[], &policy(), &settings, &calendar, now)
+            .unwrap()
+            .unwrap();
+        assert_eq!(sla.first_response.as_ref().unwrap().state, SlaState::Breached);
+        assert_eq!(sla.resolution.as_ref().unwrap().state, SlaState::AtRisk);
+
+        let results = vec![sla];
+        let compliance = compliance_by_policy(&results, &[policy()]);
+        assert_eq!(compliance[0].first_response_breached, 1);
+        assert_eq!(compliance[0].first_response_rate, Some(0.0));
+        assert_eq!(compliance[0].at_risk, 1);
+        assert_eq!(compliance[0].resolution_rate, None);
+    }
+
+    #[test]
+    fn test_validate_policies() {
+        assert!(validate_policies(&[policy()]).is_ok());
+
+        let mut no_time = policy();
+        no_time.resolution_hours = Some(0.0);
+        let mut negative = policy();
+        negative.first_response_hours = Some(-1.0);
+        let mut ratio = policy();
+        ratio.at_risk_ratio = 1.5;
+        for invalid in [no_time, negative, ratio] {
+            assert!(matches!(validate_policies(&[invalid]), Err(AppError::Config(_))));
+        }
+
+        assert!(matches!(validate_policies(&[policy(), policy()]), Err(AppError::Config(_))));
+    }
+}
@@ -0,0 +1,1086 @@
+use crate::db::{
+    clear_staged_tickets, delete_sync_checkpoint, fail_interrupted_sync_runs, finish_sync_run,
+    get_sync_checkpoint, get_sync_metadata, reconcile_scope, replace_status_transitions,
+    save_sync_checkpoint, set_sync_metadata, stage_tickets, start_sync_run, swap_staged_tickets,
+    upsert_tickets, ResyncCounts, UpsertOutcome,
+};
+use crate::errors::{AppError, DbError, JiraError};
+use crate::jira::{build_search_jql, JiraClient, RetryWait, TicketPage};
+use crate::models::{StatusTransition, SyncCheckpoint, SyncRun, SyncTrigger, Ticket};
+use crate::services::categorizer::CategoryRule;
+use crate::services::time_calc::parse_jira_timestamp;
+use crate::services::{categorize_ticket, CancellationToken};
+use chrono::{DateTime, Duration, FixedOffset, Utc};
+use chrono_tz::Tz;
+use futures_util::StreamExt;
+use rusqlite::Connection;
+use serde::Serialize;
+use std::collections::HashMap;
+use std::pin::pin;
+use std::sync::{Arc, Mutex};
+use std::time::Instant;
+
+/// Progress of a phase. While `waiting` to retry a Jira request, `current` counts down the
+/// seconds left out of `total`.
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncProgress {
+    pub phase: String,
+    pub current: usize,
+    pub total: Option<usize>,
+}
+
+impl SyncProgress {
+    fn new(phase: &str, current: usize, total: Option<usize>) -> Self {
+        SyncProgress {
+            phase: phase.to_string(),
+            current,
+            total,
+        }
+    }
+}
+
+// Incremental syncs re-fetch this far back from the newest `updated` seen, covering clock skew,
+// tickets updated mid-sync and JQL's minute precision
+const WATERMARK_OVERLAP_MINUTES: i64 = 5;
+
+#[derive(Serialize, Clone, Debug)]
+pub struct SyncSummary {
+    pub synced: usize, // tickets fetched
+    pub inserted: usize,
+    pub updated: usize,
+    pub unchanged: usize,
+    pub out_of_scope: usize,
+    pub last_sync: String,
+}
+
+/// Receives the lifecycle and progress of a sync, e.g. to forward it to the UI as events
+pub trait SyncProgressSink: Send + Sync {
+    fn started(&self);
+    fn progress(&self, progress: SyncProgress);
+    fn finished(&self, result: &Result<SyncSummary, AppError>);
+}
+
+/// Reports retry waits as the `waiting` phase and, once a wait is over, the phase it
+/// interrupted again
+struct RetryAwareSink {
+    inner: Arc<dyn SyncProgressSink>,
+    // Last progress outside a wait
+    resume: Mutex<Option<SyncProgress>>,
+}
+
+impl RetryAwareSink {
+    fn new(inner: Arc<dyn SyncProgressSink>) -> Self {
+        RetryAwareSink {
+            inner,
+            resume: Mutex::new(None),
+        }
+    }
+
+    fn retry_wait(&self, wait: RetryWait) {
+        if wait.remaining_secs > 0 {
+            self.inner.progress(SyncProgress::new(
+                "waiting",
+                wait.remaining_secs as usize,
+                Some(wait.total_secs as usize),
+            ));
+        } else if let Some(progress) = self.resume.lock().ok().and_then(|last| last.clone()) {
+            self.inner.progress(progress);
+        }
+    }
+}
+
+impl SyncProgressSink for RetryAwareSink {
+    fn started(&self) {
+        self.inner.started();
+    }
+
+    fn progress(&self, progress: SyncProgress) {
+        if let Ok(mut resume) = self.resume.lock() {
+            *resume = Some(progress.clone());
+        }
+        self.inner.progress(progress);
+    }
+
+    fn finished(&self, result: &Result<SyncSummary, AppError>) {
+        self.inner.finished(result);
+    }
+}
+
+/// Everything a sync needs besides the database
+pub struct SyncRequest {
+    pub trigger: SyncTrigger,
+    pub client: JiraClient,
+    pub scope_jql: String,
+    pub category_rules: Vec<CategoryRule>,
+}
+
+/// Runs manual and background syncs. Only one sync runs at a time; the running one can be
+/// cancelled through its token. Pages are saved as they arrive with a checkpoint, so a
+/// cancelled or interrupted sync resumes where it stopped. `last_sync_at` and the watermark
+/// only move once a sync completes. A full resync instead stages the whole scope and swaps
+/// it in at the end, leaving the stored tickets untouched until then.
+pub struct SyncEngine {
+    db: Arc<Mutex<Connection>>,
+    active: Mutex<Option<CancellationToken>>,
+}
+
+impl SyncEngine {
+    pub fn new(db: Arc<Mutex<Connection>>) -> Self {
+        // A run still marked running was cut short by a crash or forced quit
+        if let Ok(conn) = db.lock() {
+            if let Err(e) = fail_interrupted_sync_runs(&conn) {
+                log::error!("Failed to close interrupted sync runs: {}", e);
+            }
+        }
+
+        SyncEngine {
+            db,
+            active: Mutex::new(None),
+        }
+    }
+
+    pub fn is_syncing(&self) -> bool {
+        self.active.lock().map(|active| active.is_some()).unwrap_or(false)
+    }
+
+    /// Asks the running sync to stop. Returns false when no sync is running.
+    pub fn cancel(&self) -> bool {
+        match self.active.lock().ok().and_then(|active| active.clone()) {
+            Some(cancel) => {
+                log::info!("Sync cancellation requested");
+                cancel.cancel();
+                true
+            }
+            None => false,
+        }
+    }
+
+    pub async fn run(
+        &self,
+        request: SyncRequest,
+        sink: Arc<dyn SyncProgressSink>,
+    ) -> Result<SyncSummary, AppError> {
+        let mut active = self.begin(sink.clone())?;
+        sink.started();
+
+        let result = match self.record_start(request.trigger).await {
+            Ok(run) => {
+                active.run = Some(run.clone());
+                let result = self.perform(request, sink.clone(), &active.cancel).await;
+                if let Err(e) = self.record_finish(&run, &result).await {
+                    log::error!("Failed to record sync run: {}", e);
+                }
+                active.run = None;
+                result
+            }
+            Err(e) => Err(e),
+        };
+
+        active.finish(&result);
+        result
+    }
+
+    fn begin(&self, sink: Arc<dyn SyncProgressSink>) -> Result<ActiveSync<'_>, AppError> {
+        let mut active = self
+            .active
+            .lock()
+            .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+        if active.is_some() {
+            return Err(AppError::SyncAlreadyInProgress);
+        }
+
+        let cancel = CancellationToken::new();
+        *active = Some(cancel.clone());
+        Ok(ActiveSync {
+            engine: self,
+            cancel,
+            sink: Some(sink),
+            run: None,
+        })
+    }
+
+    async fn record_start(&self, trigger: SyncTrigger) -> Result<StartedRun, AppError> {
+        let started = Instant::now();
+        let started_at = Utc::now().to_rfc3339();
+        let recorded_at = started_at.clone();
+        let id = self
+            .with_db(move |conn| start_sync_run(conn, trigger, &recorded_at))
+            .await?;
+        Ok(StartedRun {
+            id,
+            trigger,
+            started_at,
+            started,
+        })
+    }
+
+    async fn record_finish(
+        &self,
+        run: &StartedRun,
+        result: &Result<SyncSummary, AppError>,
+    ) -> Result<(), AppError> {
+        let run = finished_run(run, result);
+        self.with_db(move |conn| finish_sync_run(conn, &run)).await
+    }
+
+    /// Runs `f` with the locked connection on the blocking thread pool
+    async fn with_db<T, F>(&self, f: F) -> Result<T, AppError>
+    where
+        T: Send + 'static,
+        F: FnOnce(&mut Connection) -> Result<T, AppError> + Send + 'static,
+    {
+        let db_clone = self.db.clone();
+        tauri::async_runtime::spawn_blocking(move || {
+            let mut conn = db_clone
+                .lock()
+                .map_err(|_| AppError::Internal("Lock failed".to_string()))?;
+            f(&mut conn)
+        })
+        .await
+        .map_err(|_| AppError::Internal("Task join failed".to_string()))?
+    }
+
+    async fn perform(
+        &self,
+        request: SyncRequest,
+        sink: Arc<dyn SyncProgressSink>,
+        cancel: &CancellationToken,
+    ) -> Result<SyncSummary, AppError> {
+        let SyncRequest {
+            trigger,
+            client,
+            scope_jql,
+            category_rules,
+        } = request;
+
+        let sink = Arc::new(RetryAwareSink::new(sink));
+        let retry_sink = sink.clone();
+        let client = client
+            .with_retry_listener(Arc::new(move |wait: RetryWait| retry_sink.retry_wait(wait)))
+            .with_cancellation(cancel.clone());
+
+        if trigger == SyncTrigger::FullResync {
+            return self
+                .resync(&client, &scope_jql, &category_rules, &*sink, cancel)
+                .await;
+        }
+
+        let scope = scope_jql.clone();
+        let saved = self
+            .with_db(move |conn| get_sync_checkpoint(conn, &scope))
+            .await?;
+        let mut checkpoint = match saved {
+            Some(checkpoint) => {
+                log::info!("Resuming sync after {} saved pages", checkpoint.pages);
+                checkpoint
+            }
+            None => self.new_checkpoint(&client, &scope_jql).await?,
+        };
+
+        let mut fetched = 0;
+        let mut counts = SaveCounts::default();
+
+        // A checkpoint with saved pages but no page token only misses the final step
+        'restart: while checkpoint.pages == 0 || checkpoint.next_page_token.is_some() {
+            let jql = checkpoint.jql.clone();
+            let resuming = checkpoint.next_page_token.is_some();
+            let total = approximate_total(&client, &jql).await?;
+            sink.progress(fetching_progress(checkpoint.tickets as usize, total));
+
+            let pages = client.ticket_pages(&jql, checkpoint.next_page_token.clone(), cancel);
+            let mut pages = pin!(pages);
+            while let Some(page) = pages.next().await {
+                let TicketPage {
+                    mut tickets,
+                    transitions,
+                    next_page_token,
+                } = match page {
+                    Ok(page) => page,
+                    // Page tokens don't live forever; start over rather than fail every sync
+                    Err(AppError::JiraApi(JiraError::ApiError { status: 400, .. }))
+                        if resuming && fetched == 0 =>
+                    {
+                        log::warn!("Sync checkpoint expired, starting over");
+                        checkpoint = self.new_checkpoint(&client, &scope_jql).await?;
+                        continue 'restart;
+                    }
+                    Err(e) => return Err(e),
+                };
+                categorize_tickets(&mut tickets, &category_rules);
+                fetched += tickets.len();
+
+                checkpoint.next_page_token = next_page_token;
+                checkpoint.pages += 1;
+                checkpoint.tickets += tickets.len() as i64;
+                checkpoint.max_updated = max_updated(&tickets)
+                    .into_iter()
+                    .chain(checkpoint.max_updated.as_deref().and_then(parse_jira_timestamp))
+                    .max()
+                    .map(|newest| newest.to_rfc3339());
+
+                let page_checkpoint = checkpoint.clone();
+                let page_cancel = cancel.clone();
+                let page_counts = self
+                    .with_db(move |conn| {
+                        save_page(conn, &tickets, transitions, &page_checkpoint, &page_cancel)
+                    })
+                    .await?;
+                counts.add(&page_counts);
+
+                sink.progress(fetching_progress(checkpoint.tickets as usize, total));
+            }
+        }
+
+        // Keys still in scope, so tickets deleted or moved away in Jira can be tombstoned
+        let in_scope_keys = client.fetch_scope_keys(&scope_jql, cancel).await?;
+
+        sink.progress(SyncProgress::new("saving", fetched, Some(fetched)));
+
+        let cancel = cancel.clone();
+        let now = Utc::now().to_rfc3339();
+        let now_clone = now.clone();
+        let out_of_scope = self
+            .with_db(move |conn| {
+                finish_sync(conn, &checkpoint, &in_scope_keys, &cancel, &now_clone)
+            })
+            .await?;
+
+        if out_of_scope > 0 {
+            log::info!("Sync tombstoned {} out-of-scope tickets", out_of_scope);
+        }
+
+        Ok(SyncSummary {
+            synced: fetched,
+            inserted: counts.inserted,
+            updated: counts.updated,
+            unchanged: counts.unchanged,
+            out_of_scope,
+            last_sync: now,
+        })
+    }
+
+    /// Re-fetches the whole scope into the staging tables, ignoring the watermark and any
+    /// checkpoint, and swaps them with the stored tickets once everything has arrived
+    async fn resync(
+        &self,
+        client: &JiraClient,
+        scope_jql: &str,
+        category_rules: &[CategoryRule],
+        sink: &dyn SyncProgressSink,
+        cancel: &CancellationToken,
+    ) -> Result<SyncSummary, AppError> {
+        // Leftovers of a cancelled or interrupted resync
+        self.with_db(|conn| clear_staged_tickets(conn)).await?;
+
+        let jql = build_search_jql(scope_jql, None);
+        let total = approximate_total(client, &jql).await?;
+        let mut fetched = 0;
+        let mut newest = None;
+        sink.progress(fetching_progress(0, total));
+
+        let mut pages = pin!(client.ticket_pages(&jql, None, cancel));
+        while let Some(page) = pages.next().await {
+            let TicketPage {
+                mut tickets,
+                transitions,
+                ..
+            } = page?;
+            categorize_tickets(&mut tickets, category_rules);
+            fetched += tickets.len();
+            newest = newest.max(max_updated(&tickets));
+
+            self.with_db(move |conn| {
+                let tx = conn.transaction().map_err(DbError::from)?;
+                stage_tickets(&tx, &tickets, &transitions)?;
+                tx.commit().map_err(DbError::from)?;
+                Ok(())
+            })
+            .await?;
+
+            sink.progress(fetching_progress(fetched, total));
+        }
+
+        sink.progress(SyncProgress::new("saving", fetched, Some(fetched)));
+
+        let scope_jql = scope_jql.to_string();
+        let newest = newest.map(|newest| newest.to_rfc3339());
+        let cancel = cancel.clone();
+        let now = Utc::now().to_rfc3339();
+        let now_clone = now.clone();
+        let counts = self
+            .with_db(move |conn| {
+                finish_resync(conn, &scope_jql, newest.as_deref(), &cancel, &now_clone)
+            })
+            .await?;
+
+        log::info!(
+            "Full resync: {} new, {} changed, {} unchanged, {} removed",
+            counts.inserted,
+            counts.updated,
+            counts.unchanged,
+            counts.removed
+        );
+
+        Ok(SyncSummary {
+            synced: fetched,
+            inserted: counts.inserted,
+            updated: counts.updated,
+            unchanged: counts.unchanged,
+            out_of_scope: counts.removed,
+            last_sync: now,
+        })
+    }
+
+    /// Starts a sync of `scope_jql` from its watermark, or from scratch on the first sync
+    async fn new_checkpoint(
+        &self,
+        client: &JiraClient,
+        scope_jql: &str,
+    ) -> Result<SyncCheckpoint, AppError> {
+        // Newest `updated` seen by earlier syncs of this scope
+        let key = watermark_key(scope_jql);
+        let stored_watermark = self
+            .with_db(move |conn| get_sync_metadata(conn, &key))
+            .await?;
+
+        let updated_since = match stored_watermark.as_deref().and_then(parse_jira_timestamp) {
+            Some(watermark) => Some(jql_watermark(watermark, self.user_timezone(client).await?)),
+            None => None,
+        };
+
+        Ok(SyncCheckpoint {
+            scope_jql: scope_jql.to_string(),
+            jql: build_search_jql(scope_jql, updated_since.as_deref()),
+            next_page_token: None,
+            pages: 0,
+            tickets: 0,
+            max_updated: stored_watermark,
+            started_at: Utc::now().to_rfc3339(),
+        })
+    }
+
+    /// The Jira user's timezone, asked for on the first incremental sync of each account and
+    /// cached in the sync metadata. An empty value records that Jira didn't report one.
+    async fn user_timezone(&self, client: &JiraClient) -> Result<Option<Tz>, AppError> {
+        let key = user_timezone_key(client);
+        let cached = self
+            .with_db(move |conn| get_sync_metadata(conn, &key))
+            .await?;
+        if let Some(name) = cached {
+            return Ok(parse_timezone(&name));
+        }
+
+        let name = client.fetch_user_timezone().await?.unwrap_or_default();
+        let key = user_timezone_key(client);
+        let stored = name.clone();
+        self.with_db(move |conn| set_sync_metadata(conn, &key, &stored))
+            .await?;
+        Ok(parse_timezone(&name))
+    }
+}
+
+/// Marks the engine busy for as long as a sync runs. When the sync is dropped before it
+/// finishes, e.g. because the task running it was aborted, the guard still frees the engine
+/// and records the run as cancelled.
+struct ActiveSync<'a> {
+    engine: &'a SyncEngine,
+    cancel: CancellationToken,
+    sink: Option<Arc<dyn SyncProgressSink>>,
+    run: Option<StartedRun>, // recorded as running, not yet as finished
+}
+
+/// A sync run as recorded when it started
+#[derive(Clone)]
+struct StartedRun {
+    id: i64,
+    trigger: SyncTrigger,
+    started_at: String,
+    started: Instant,
+}
+
+impl ActiveSync<'_> {
+    /// Frees the engine, then reports the result
+    fn finish(mut self, result: &Result<SyncSummary, AppError>) {
+        let sink = self.sink.take();
+        drop(self);
+        if let Some(sink) = sink {
+            sink.finished(result);
+        }
+    }
+}
+
+impl Drop for ActiveSync<'_> {
+    fn drop(&mut self) {
+        let cancelled = Err(AppError::SyncCancelled);
+        if let Some(run) = self.run.take() {
+            let run = finished_run(&run, &cancelled);
+            match self.engine.db.lock() {
+                Ok(conn) => {
+                    if let Err(e) = finish_sync_run(&conn, &run) {
+                        log::error!("Failed to record sync run: {}", e);
+                    }
+                }
+                Err(_) => log::error!("Failed to record sync run: lock failed"),
+            }
+        }
+
+        if let Ok(mut active) = self.engine.active.lock() {
+            *active = None;
+        }
+        if let Some(sink) = self.sink.take() {
+            sink.finished(&cancelled);
+        }
+    }
+}
+
+fn categorize_tickets(tickets: &mut [Ticket], category_rules: &[CategoryRule]) {
+    for ticket in tickets.iter_mut() {
+        ticket.category = categorize_ticket(ticket, category_rules);
+    }
+}
+
+/// Fetching progress, never reporting fewer tickets in total than already fetched
+fn fetching_progress(current: usize, total: Option<usize>) -> SyncProgress {
+    SyncProgress::new("fetching", current, total.map(|total| total.max(current)))
+}
+
+/// Expected number of tickets the sync fetches, or `None` if Jira can't estimate it
+async fn approximate_total(client: &JiraClient, jql: &str) -> Result<Option<usize>, AppError> {
+    match client.approximate_count(jql).await {
+        Ok(count) => Ok(Some(count as usize)),
+        // Only progress depends on the count, so a failed estimate shouldn't fail the sync
+        Err(AppError::JiraApi(e)) => {
+            log::warn!("Failed to estimate sync size: {}", e);
+            Ok(None)
+        }
+        Err(e) => Err(e),
+    }
+}
+
+fn watermark_key(scope_jql: &str) -> String {
+    format!("watermark:{}", scope_jql)
+}
+
+/// Newest `updated` among the fetched tickets
+fn max_updated(tickets: &[Ticket]) -> Option<DateTime<FixedOffset>> {
+    tickets
+        .iter()
+        .filter_map(|ticket| parse_jira_timestamp(&ticket.updated_at))
+        .max()
+}
+
+/// The watermark minus the safety overlap, as a JQL date in the Jira user's timezone
+fn jql_watermark(watermark: DateTime<FixedOffset>, user_tz: Option<Tz>) -> String {
+    let since = watermark - Duration::minutes(WATERMARK_OVERLAP_MINUTES);
+    match user_tz {
+        Some(tz) => since.with_timezone(&tz).format("%Y-%m-%d %H:%M").to_string(),
+        None => since.with_timezone(&Utc).format("%Y-%m-%d %H:%M").to_string(),
+    }
+}
+
+fn user_timezone_key(client: &JiraClient) -> String {
+    format!("user_timezone:{}", client.account())
+}
+
+fn parse_timezone(name: &str) -> Option<Tz> {
+    if name.is_empty() {
+        return None;
+    }
+    match name.parse::<Tz>() {
+        Ok(tz) => Some(tz),
+        Err(_) => {
+            log::warn!("Unknown Jira user timezone '{}', using UTC", name);
+            None
+        }
+    }
+}
+
+fn finished_run(started: &StartedRun, result: &Result<SyncSummary, AppError>) -> SyncRun {
+    let mut run = SyncRun {
+        id: started.id,
+        trigger: started.trigger.as_str().to_string(),
+        status: "succeeded".to_string(),
+        started_at: started.started_at.clone(),
+        finished_at: Some(Utc::now().to_rfc3339()),
+        duration_ms: Some(started.started.elapsed().as_millis() as i64),
+        fetched: 0,
+        inserted: 0,
+        updated: 0,
+        unchanged: 0,
+        out_of_scope: 0,
+        error: None,
+    };
+
+    match result {
+        Ok(summary) => {
+            run.fetched = summary.synced as i64;
+            run.inserted = summary.inserted as i64;
+            run.updated = summary.updated as i64;
+            run.unchanged = summary.unchanged as i64;
+            run.out_of_scope = summary.out_of_scope as i64;
+        }
+        Err(AppError::SyncCancelled) => {
+            run.status = "cancelled".to_string();
+        }
+        Err(e) => {
+            run.status = "failed".to_string();
+            run.error = Some(e.to_string());
+        }
+    }
+
+    run
+}
+
+#[derive(Debug, Default, PartialEq)]
+struct SaveCounts {
+    inserted: usize,
+    updated: usize,
+    unchanged: usize,
+}
+
+impl SaveCounts {
+    fn add(&mut self, other: &SaveCounts) {
+        self.inserted += other.inserted;
+        self.updated += other.updated;
+        self.unchanged += other.unchanged;
+    }
+}
+
+/// Writes one page of tickets and transitions together with the checkpoint after it, so an
+/// interrupted sync resumes with the next page. A cancelled page is rolled back.
+fn save_page(
+    conn: &mut Connection,
+    tickets: &[Ticket],
+    transitions: Vec<StatusTransition>,
+    checkpoint: &SyncCheckpoint,
+    cancel: &CancellationToken,
+) -> Result<SaveCounts, AppError> {
+    let mut transitions_by_key: HashMap<String, Vec<StatusTransition>> = HashMap::new();
+    for transition in transitions {
+        transitions_by_key
+            .entry(transition.jira_key.clone())
+            .or_default()
+            .push(transition);
+    }
+
+    // Returning early drops the transaction, which rolls back everything written so far
+    cancel.check()?;
+    let tx = conn.transaction().map_err(DbError::from)?;
+
+    let mut counts = SaveCounts::default();
+    for outcome in upsert_tickets(&tx, tickets)? {
+        match outcome {
+            UpsertOutcome::Inserted => counts.inserted += 1,
+            UpsertOutcome::Updated => counts.updated += 1,
+            UpsertOutcome::Unchanged => counts.unchanged += 1,
+        }
+    }
+    for ticket in tickets {
+        cancel.check()?;
+        replace_status_transitions(
+            &tx,
+            &ticket.jira_key,
+            transitions_by_key
+                .get(&ticket.jira_key)
+                .map(Vec::as_slice)
+                .unwrap_or_default(),
+        )?;
+    }
+    save_sync_checkpoint(&tx, checkpoint)?;
+
+    cancel.check()?;
+    tx.commit().map_err(DbError::from)?;
+
+    Ok(counts)
+}
+
+/// Completes a sync: tombstones tickets that left the scope, moves `last_sync_at` and the
+/// scope's watermark, and drops the checkpoint. Returns the number of tickets tombstoned.
+fn finish_sync(
+    conn: &mut Connection,
+    checkpoint: &SyncCheckpoint,
+    in_scope_keys: &[String],
+    cancel: &CancellationToken,
+    now: &str,
+) -> Result<usize, AppError> {
+    cancel.check()?;
+    let tx = conn.transaction().map_err(DbError::from)?;
+
+    let out_of_scope = reconcile_scope(&tx, in_scope_keys, now)?;
+    set_sync_metadata(&tx, "last_sync_at", now)?;
+    if let Some(max_updated) = &checkpoint.max_updated {
+        set_sync_metadata(&tx, &watermark_key(&checkpoint.scope_jql), max_updated)?;
+    }
+    delete_sync_checkpoint(&tx, &checkpoint.scope_jql)?;
+
+    cancel.check()?;
+    tx.commit().map_err(DbError::from)?;
+
+    Ok(out_of_scope)
+}
+
+/// Completes a full resync: swaps in the staged tickets, moves `last_sync_at` and the
+/// scope's watermark, and drops any incremental checkpoint the resync superseded
+fn finish_resync(
+    conn: &mut Connection,
+    scope_jql: &str,
+    newest: Option<&str>,
+    cancel: &CancellationToken,
+    now: &str,
+) -> Result<ResyncCounts, AppError> {
+    cancel.check()?;
+    let tx = conn.transaction().map_err(DbError::from)?;
+
+    let counts = swap_staged_tickets(&tx, now)?;
+    set_sync_metadata(&tx, "last_sync_at", now)?;
+    if let Some(newest) = newest {
+        set_sync_metadata(&tx, &watermark_key(scope_jql), newest)?;
+    }
+    delete_sync_checkpoint(&tx, scope_jql)?;
+
+    cancel.check()?;
+    tx.commit().map_err(DbError::from)?;
+
+    Ok(counts)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::db::{get_tickets, initialize_database};
+    use crate::jira::fake_server::FakeJira;
+    use crate::services::categorizer::{MatchMode, RuleCondition};
+
+    fn ticket(jira_key: &str, summary: &str) -> Ticket {
+        Ticket {
+            id: 0,
+            jira_key: jira_key.to_string(),
+            summary: summary.to_string(),
+            status: "Open".to_string(),
+            priority: "Medium".to_string(),
+            issue_type: "Task".to_string(),
+            assignee: None,
+            reporter: None,
+            created_at: "2025-01-06T08:00:00.000+0000".to_string(),
+            updated_at: "2025-01-06T08:00:00.000+0000".to_string(),
+            resolved_at: None,
+            labels: String::new(),
+            project_key: "TEST".to_string(),
+            category: None,
+        }
+    }
+
+    fn test_engine() -> SyncEngine {
+        let conn = Connection::open_in_memory().unwrap();
+        initialize_database(&conn).unwrap();
+        SyncEngine::new(Arc::new(Mutex::new(conn)))
+    }
+
+    #[derive(Default)]
+    struct RecordingSink {
+        events: Mutex<Vec<String>>,
+    }
+
+    impl RecordingSink {
+        fn events(&self) -> Vec<String> {
+            self.events.lock().unwrap().clone()
+        }
+    }
+
+    impl SyncProgressSink for RecordingSink {
+        fn started(&self) {
+            self.events.lock().unwrap().push("started".to_string());
+        }
+
+        fn progress(&self, progress: SyncProgress) {
+            let event = format!("{} {}/{:?}", progress.phase, progress.current, progress.total);
+            self.events.lock().unwrap().push(event);
+        }
+
+        fn finished(&self, result: &Result<SyncSummary, AppError>) {
+            let event = match result {
+                Ok(summary) => format!("finished {}", summary.synced),
+                Err(e) => format!("failed {}", e),
+            };
+            self.events.lock().unwrap().push(event);
+        }
+    }
+
+    fn runtime() -> tokio::runtime::Runtime {
+        tokio::runtime::Builder::new_multi_thread().enable_all().build().unwrap()
+    }
+
+    fn request(jira_url: &str, trigger: SyncTrigger) -> SyncRequest {
+        SyncRequest {
+            trigger,
+            client: JiraClient::new(jira_url, "user@example.com", "token").unwrap(),
+            scope_jql: "project = TEST".to_string(),
+            category_rules: Vec::new(),
+        }
+    }
+
+    fn issue_json(key: &str) -> serde_json::Value {
+        serde_json::json!({
+            "key": key,
+            "fields": {
+                "summary": "Printer jammed",
+                "status": { "name": "Open" },
+                "priority": { "name": "Medium" },
+                "issuetype": { "name": "Task" },
+                "assignee": null,
+                "reporter": null,
+                "created": "2025-01-06T08:00:00.000+0000",
+                "updated": "2025-01-06T08:00:00.000+0000",
+                "resolutiondate": null,
+                "labels": [],
+                "project": { "key": "TEST" }
+            },
+            "changelog": { "total": 0, "histories": [] }
+        })
+    }
+
+    #[test]
+    fn test_sync_reports_progress() {
+        // Two pages of one ticket each; the second page is rate limited once
+        let rate_limited = std::sync::atomic::AtomicBool::new(false);
+        let jira = FakeJira::start(move |request| {
+            let body: serde_json::Value = serde_json::from_str(&request.body).unwrap();
+            let response = match request.path.as_str() {
+                "/search/approximate-count" => serde_json::json!({ "count": 2 }),
+                _ if body["maxResults"] == 5000 => serde_json::json!({
+                    "issues": [{ "key": "TEST-1" }, { "key": "TEST-2" }]
+                }),
+                _ if body["nextPageToken"].is_null() => serde_json::json!({
+                    "issues": [issue_json("TEST-1")],
+                    "nextPageToken": "page-2"
+                }),
+                _ if !rate_limited.swap(true, std::sync::atomic::Ordering::SeqCst) => {
+                    return (429, "{}".to_string());
+                }
+                _ => serde_json::json!({ "issues": [issue_json("TEST-2")] }),
+            };
+            (200, response.to_string())
+        });
+
+        let engine = test_engine();
+        let sink = Arc::new(RecordingSink::default());
+        let request = request(jira.url(), SyncTrigger::Manual);
+        let summary = runtime().block_on(engine.run(request, sink.clone())).unwrap();
+
+        assert_eq!(summary.inserted, 2);
+        assert_eq!(
+            sink.events(),
+            vec![
+                "started",
+                "fetching 0/Some(2)",
+                "fetching 1/Some(2)",
+                "waiting 1/Some(1)",
+                "fetching 1/Some(2)",
+                "fetching 2/Some(2)",
+                "saving 2/Some(2)",
+                "finished 2",
+            ]
+        );
+        assert!(!engine.is_syncing());
+    }
+
+    #[test]
+    fn test_only_one_sync_at_a_time() {
+        let engine = test_engine();
+        assert!(!engine.cancel());
+
+        let sink = Arc::new(RecordingSink::default());
+        let active = engine.begin(sink.clone()).unwrap();
+        assert!(engine.is_syncing());
+        assert!(matches!(engine.begin(sink.clone()), Err(AppError::SyncAlreadyInProgress)));
+
+        assert!(engine.cancel());
+        assert!(active.cancel.is_cancelled());
+
+        active.finish(&Err(AppError::SyncCancelled));
+        assert!(!engine.is_syncing());
+        assert!(engine.begin(sink).is_ok());
+    }
+
+    #[test]
+    fn test_aborted_sync_frees_engine() {
+        // Never answers, so the sync hangs on its first request
+        let jira = std::net::TcpListener::bind("127.0.0.1:0").unwrap();
+        let jira_url = format!("http://{}", jira.local_addr().unwrap());
+
+        let engine = Arc::new(test_engine());
+        let sink = Arc::new(RecordingSink::default());
+        runtime().block_on(async {
+            let sync_engine = engine.clone();
+            let sync_sink: Arc<dyn SyncProgressSink> = sink.clone();
+            let handle = tauri::async_runtime::spawn(async move {
+                let request = request(&jira_url, SyncTrigger::Scheduled);
+                let _ = sync_engine.run(request, sync_sink).await;
+            });
+
+            // Wait for the sync's first request to Jira
+            jira.set_nonblocking(true).unwrap();
+            let _connection = loop {
+                match jira.accept() {
+                    Ok(connection) => break connection,
+                    Err(_) => tokio::time::sleep(std::time::Duration::from_millis(10)).await,
+                }
+            };
+            assert!(engine.is_syncing());
+
+            handle.abort();
+            let _ = handle.await;
+        });
+
+        assert!(!engine.is_syncing());
+        let conn = engine.db.lock().unwrap();
+        let run = crate::db::get_sync_runs(&conn, 1).unwrap().remove(0);
+        assert_eq!(run.status, "cancelled");
+        assert_eq!(sink.events(), vec!["started", "failed Sync was cancelled"]);
+    }
+
+    #[test]
+    fn test_finished_run_keeps_start() {
+        let started = StartedRun {
+            id: 7,
+            trigger: SyncTrigger::Manual,
+            started_at: "2025-01-07T00:00:00+00:00".to_string(),
+            started: Instant::now(),
+        };
+        let run = finished_run(&started, &Err(AppError::Internal("boom".to_string())));
+        assert_eq!(run.id, 7);
+        assert_eq!(run.started_at, "2025-01-07T00:00:00+00:00");
+        assert_eq!(run.status, "failed");
+        assert!(run.finished_at.is_some());
+    }
+
+    #[test]
+    fn test_categorize_tickets() {
+        let rules = vec![CategoryRule {
+            id: "1".to_string(),
+            name: "Printing".to_string(),
+            color: "#000000".to_string(),
+            conditions: vec![RuleCondition {
+                field: "summary".to_string(),
+                operator: "contains".to_string(),
+                value: "printer".to_string(),
+                case_sensitive: false,
+            }],
+            match_mode: MatchMode::Any,
+        }];
+        let mut tickets = vec![ticket("TEST-1", "Printer jammed"), ticket("TEST-2", "VPN down")];
+
+        categorize_tickets(&mut tickets, &rules);
+
+        assert_eq!(tickets[0].category.as_deref(), Some("Printing"));
+        assert_eq!(tickets[1].category, None);
+    }
+
+    fn checkpoint(next_page_token: Option<&str>) -> SyncCheckpoint {
+        SyncCheckpoint {
+            scope_jql: "project = TEST".to_string(),
+            jql: "(project = TEST) ORDER BY updated ASC".to_string(),
+            next_page_token: next_page_token.map(str::to_string),
+            pages: 1,
+            tickets: 1,
+            max_updated: Some("2025-01-06T08:00:00+00:00".to_string()),
+            started_at: "2025-01-07T00:00:00+00:00".to_string(),
+        }
+    }
+
+    #[test]
+    fn test_cancelled_page_rolls_back() {
+        let engine = test_engine();
+        let mut conn = engine.db.lock().unwrap();
+        let tickets = vec![ticket("TEST-1", "Printer jammed")];
+        let checkpoint = checkpoint(Some("page-2"));
+
+        let cancel = CancellationToken::new();
+        cancel.cancel();
+        let result = save_page(&mut conn, &tickets, Vec::new(), &checkpoint, &cancel);
+        assert!(matches!(result, Err(AppError::SyncCancelled)));
+        assert!(get_tickets(&conn).unwrap().is_empty());
+        assert!(get_sync_checkpoint(&conn, "project = TEST").unwrap().is_none());
+
+        let cancel = CancellationToken::new();
+        let counts = save_page(&mut conn, &tickets, Vec::new(), &checkpoint, &cancel).unwrap();
+        assert_eq!(counts.inserted, 1);
+        assert_eq!(get_tickets(&conn).unwrap().len(), 1);
+
+        // The next sync resumes from the saved page token
+        let saved = get_sync_checkpoint(&conn, "project = TEST").unwrap().unwrap();
+        assert_eq!(saved.next_page_token.as_deref(), Some("page-2"));
+        assert_eq!(saved.pages, 1);
+        assert_eq!(get_sync_metadata(&conn, "last_sync_at").unwrap(), None);
+    }
+
+    #[test]
+    fn test_finish_sync_moves_watermark() {
+        let engine = test_engine();
+        let mut conn = engine.db.lock().unwrap();
+        let tickets = vec![ticket("TEST-1", "Printer jammed")];
+        let checkpoint = checkpoint(None);
+        let cancel = CancellationToken::new();
+        save_page(&mut conn, &tickets, Vec::new(), &checkpoint, &cancel).unwrap();
+
+        let keys = vec!["TEST-1".to_string()];
+        let out_of_scope = finish_sync(&mut conn, &checkpoint, &keys, &cancel, "2025-01-07");
+
+        assert_eq!(out_of_scope.unwrap(), 0);
+        assert!(get_sync_checkpoint(&conn, "project = TEST").unwrap().is_none());
+        assert_eq!(
+            get_sync_metadata(&conn, "last_sync_at").unwrap().as_deref(),
+            Some("2025-01-07")
+        );
+        assert_eq!(
+            get_sync_metadata(&conn, "watermark:project = TEST").unwrap(),
+            checkpoint.max_updated
+        );
+    }
+
+    #[test]
+    fn test_user_timezone_fetched_once() {
+        let lookups = Arc::new(std::sync::atomic::AtomicUsize::new(0));
+        let counter = lookups.clone();
+        let jira = FakeJira::start(move |request| match request.path.as_str() {
+            "/myself" => {
+                counter.fetch_add(1, std::sync::atomic::Ordering::SeqCst);
+                (200, r#"{"timeZone":"Europe/Berlin"}"#.to_string())
+            }
+            _ => (400, "{}".to_string()),
+        });
+
+        let engine = test_engine();
+        let conn = engine.db.lock().unwrap();
+        let watermark = "2025-01-06T10:30:45.000+0100";
+        set_sync_metadata(&conn, "watermark:project = TEST", watermark).unwrap();
+        drop(conn);
+
+        let client = request(jira.url(), SyncTrigger::Manual).client;
+        for _ in 0..2 {
+            let checkpoint = runtime()
+                .block_on(engine.new_checkpoint(&client, "project = TEST"))
+                .unwrap();
+            assert!(checkpoint.jql.contains("2025-01-06 10:25"));
+        }
+        assert_eq!(lookups.load(std::sync::atomic::Ordering::SeqCst), 1);
+    }
+
+    #[test]
+    fn test_watermark_from_newest_update() {
+        let mut older = ticket("TEST-1", "Older");
+        older.updated_at = "2025-01-06T08:00:00.000+0000".to_string();
+        let mut newer = ticket("TEST-2", "Newer");
+        newer.updated_at = "2025-01-06T10:30:45.000+0100".to_string();
+
+        let watermark = max_updated(&[older, newer]).unwrap();
+        assert_eq!(watermark.to_rfc3339(), "2025-01-06T10:30:45+01:00");
+
+        // Minus the overlap, in the Jira user's timezone, at JQL's minute precision
+        let berlin: Tz = "Europe/Berlin".parse().unwrap();
+        assert_eq!(jql_watermark(watermark, Some(berlin)), "2025-01-06 10:25");
+        let new_york: Tz = "America/New_York".parse().unwrap();
+        assert_eq!(jql_watermark(watermark, Some(new_york)), "2025-01-06 04:25");
+        assert_eq!(jql_watermark(watermark, None), "2025-01-06 09:25");
+    }
+}
@@ -1,57 +1,422 @@
-use chrono::{Datelike, Duration, NaiveDateTime, NaiveTime, Weekday};
-use crate::errors::AppError;
+use chrono::{
+    DateTime, Datelike, Duration, FixedOffset, LocalResult, NaiveDate, NaiveDateTime, NaiveTime,
+    TimeZone, Weekday,
+};
+use chrono_tz::Tz;
+use crate::errors::{AppError, ScheduleError};
+use crate::models::Holiday;
+use serde::{Deserialize, Serialize};
+use std::collections::HashSet;
+
+/// Working hours used for business-time metrics
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct BusinessHoursSettings {
+    pub work_start_hour: u32,
+    pub work_end_hour: u32,
+    // IANA name such as "Europe/Berlin". Without it, each timestamp's own offset is used.
+    #[serde(default)]
+    pub timezone: Option<String>,
+    // Per-weekday schedule; overrides work_start_hour/work_end_hour when set
+    #[serde(default)]
+    pub schedule: Option<WorkSchedule>,
+    // Priorities covered around the clock, e.g. ["Critical"]
+    #[serde(default)]
+    pub always_on_priorities: Vec<String>,
+}
+
+impl BusinessHoursSettings {
+    pub fn team_timezone(&self) -> Result<Option<Tz>, AppError> {
+        self.timezone
+            .as_deref()
+            .map(|name| {
+                name.parse::<Tz>()
+                    .map_err(|_| AppError::Config(format!("Unknown timezone: {}", name)))
+            })
+            .transpose()
+    }
+
+    pub fn validate(&self) -> Result<(), AppError> {
+        self.team_timezone()?;
+        match &self.schedule {
+            Some(schedule) => schedule.validate()?,
+            None => {
+                WorkSchedule::weekdays(self.work_start_hour, self.work_end_hour)?;
+            }
+        }
+        Ok(())
+    }
+
+    /// The schedule that applies to tickets of the given priority
+    pub fn schedule_for(&self, priority: &str) -> Result<WorkSchedule, ScheduleError> {
+        if self.always_on_priorities.iter().any(|p| p == priority) {
+            return Ok(WorkSchedule::always_on());
+        }
+
+        self.work_schedule()
+    }
+
+    /// The team's regular working hours, ignoring 24/7 priorities
+    pub fn work_schedule(&self) -> Result<WorkSchedule, ScheduleError> {
+        match &self.schedule {
+            Some(schedule) => {
+                schedule.validate()?;
+                Ok(schedule.clone())
+            }
+            None => WorkSchedule::weekdays(self.work_start_hour, self.work_end_hour),
+        }
+    }
+}
+
+impl Default for BusinessHoursSettings {
+    fn default() -> Self {
+        BusinessHoursSettings {
+            work_start_hour: 9,
+            work_end_hour: 17,
+            timezone: None,
+            schedule: None,
+            always_on_priorities: Vec::new(),
+        }
+    }
+}
+
+/// A working period within a day, e.g. 08:30-12:00. An end of 00:00 means midnight at the
+/// end of the day, so 22:00-00:00 is a two-hour shift.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct WorkInterval {
+    pub start: NaiveTime,
+    pub end: NaiveTime,
+}
+
+impl WorkInterval {
+    fn ends_at_midnight(&self) -> bool {
+        self.end == NaiveTime::MIN
+    }
+
+    fn contains(&self, time: NaiveTime) -> bool {
+        self.start <= time && (self.ends_at_midnight() || time < self.end)
+    }
+
+    /// Wall-clock start and end of the interval on `date`
+    fn on(&self, date: NaiveDate) -> (NaiveDateTime, NaiveDateTime) {
+        let end = if self.ends_at_midnight() {
+            date.succ_opt().map_or(NaiveDateTime::MAX, |next| next.and_time(self.end))
+        } else {
+            date.and_time(self.end)
+        };
+        (date.and_time(self.start), end)
+    }
+}
+
+/// Working intervals for each weekday. Days without intervals are non-working days.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+pub struct WorkSchedule {
+    #[serde(default)]
+    pub monday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub tuesday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub wednesday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub thursday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub friday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub saturday: Vec<WorkInterval>,
+    #[serde(default)]
+    pub sunday: Vec<WorkInterval>,
+    // 24/7 coverage: every minute counts, including weekends and holidays
+    #[serde(default)]
+    pub always_on: bool,
+}
+
+impl WorkSchedule {
+    /// Monday to Friday, `work_start_hour` until `work_end_hour`
+    pub fn weekdays(work_start_hour: u32, work_end_hour: u32) -> Result<Self, ScheduleError> {
+        // Validate work hours are in valid range (0-23)
+        if work_start_hour > 23 || work_end_hour > 23 {
+            return Err(ScheduleError::InvalidHours {
+                start: work_start_hour,
+                end: work_end_hour,
+            });
+        }
+
+        if work_start_hour >= work_end_hour {
+            return Err(ScheduleError::StartNotBeforeEnd {
+                start: work_start_hour,
+                end: work_end_hour,
+            });
+        }
+
+        let interval = WorkInterval {
+            start: NaiveTime::from_hms_opt(work_start_hour, 0, 0).unwrap_or(NaiveTime::MIN),
+            end: NaiveTime::from_hms_opt(work_end_hour, 0, 0).unwrap_or(NaiveTime::MIN),
+        };
+
+        Ok(WorkSchedule {
+            monday: vec![interval],
+            tuesday: vec![interval],
+            wednesday: vec![interval],
+            thursday: vec![interval],
+            friday: vec![interval],
+            ..Default::default()
+        })
+    }
+
+    pub fn always_on() -> Self {
+        WorkSchedule {
+            always_on: true,
+            ..Default::default()
+        }
+    }
+
+    pub fn intervals(&self, weekday: Weekday) -> &[WorkInterval] {
+        match weekday {
+            Weekday::Mon => &self.monday,
+            Weekday::Tue => &self.tuesday,
+            Weekday::Wed => &self.wednesday,
+            Weekday::Thu => &self.thursday,
+            Weekday::Fri => &self.friday,
+            Weekday::Sat => &self.saturday,
+            Weekday::Sun => &self.sunday,
+        }
+    }
+
+    /// Whether a wall-clock time falls within a working interval
+    pub fn is_working_time(&self, local: NaiveDateTime) -> bool {
+        self.always_on
+            || self
+                .intervals(local.weekday())
+                .iter()
+                .any(|i| i.contains(local.time()))
+    }
+
+    /// Start of the first working interval after `local`, looking up to a week ahead
+    pub fn next_work_start(&self, local: NaiveDateTime) -> Option<NaiveDateTime> {
+        (0..=7).find_map(|offset| {
+            let date = local.date() + Duration::days(offset);
+            self.intervals(date.weekday())
+                .iter()
+                .map(|i| date.and_time(i.start))
+                .filter(|start| *start > local)
+                .min()
+        })
+    }
+
+    /// Checks that every interval ends after it starts and that a day's intervals don't overlap
+    pub fn validate(&self) -> Result<(), ScheduleError> {
+        let weekdays = [
+            Weekday::Mon,
+            Weekday::Tue,
+            Weekday::Wed,
+            Weekday::Thu,
+            Weekday::Fri,
+            Weekday::Sat,
+            Weekday::Sun,
+        ];
+
+        for weekday in weekdays {
+            let mut intervals = self.intervals(weekday).to_vec();
+            let empty = |i: &&WorkInterval| i.end <= i.start && !i.ends_at_midnight();
+            if let Some(interval) = intervals.iter().find(empty) {
+                return Err(ScheduleError::EmptyInterval {
+                    weekday,
+                    start: interval.start,
+                    end: interval.end,
+                });
+            }
+
+            intervals.sort_by_key(|i| i.start);
+            for pair in intervals.windows(2) {
+                if pair[0].ends_at_midnight() || pair[1].start < pair[0].end {
+                    return Err(ScheduleError::OverlappingIntervals {
+                        weekday,
+                        first_end: pair[0].end,
+                        second_start: pair[1].start,
+                    });
+                }
+            }
+        }
+
+        Ok(())
+    }
+}
+
+/// Public holidays and company shutdown days, on which no work is scheduled
+#[derive(Debug, Clone, Default)]
+pub struct WorkCalendar {
+    holidays: HashSet<NaiveDate>,
+}
+
+impl WorkCalendar {
+    pub fn new(holidays: &[Holiday]) -> Self {
+        WorkCalendar {
+            holidays: holidays.iter().map(|h| h.date).collect(),
+        }
+    }
+
+    pub fn is_holiday(&self, date: NaiveDate) -> bool {
+        self.holidays.contains(&date)
+    }
+}
+
+/// Reads all-day events from an iCalendar (.ics) file. Multi-day events yield one holiday per
+/// day, with DTEND treated as exclusive as the iCalendar spec requires. Timed events are skipped.
+pub fn parse_ics_holidays(content: &str) -> Result<Vec<Holiday>, AppError> {
+    // Undo line folding: continuation lines start with a space or tab
+    let unfolded = content.replace("\r\n", "\n").replace("\n ", "").replace("\n\t", "");
+
+    let mut holidays = Vec::new();
+    let mut in_event = false;
+    let mut start: Option<NaiveDate> = None;
+    let mut end: Option<NaiveDate> = None;
+    let mut name: Option<String> = None;
+    let mut all_day = false;
+
+    for line in unfolded.lines() {
+        let Some((key, value)) = line.split_once(':') else {
+            continue;
+        };
+        // Split off parameters such as ";VALUE=DATE"
+        let (key, params) = key.split_once(';').unwrap_or((key, ""));
+
+        match key {
+            "BEGIN" if value == "VEVENT" => {
+                in_event = true;
+                start = None;
+                end = None;
+                name = None;
+                all_day = false;
+            }
+            "DTSTART" if in_event => {
+                // All-day events start on a DATE; timed ones such as meetings on a DATE-TIME
+                all_day = params.split(';').any(|param| param == "VALUE=DATE")
+                    || !value.contains('T');
+                start = Some(parse_ics_date(value)?);
+            }
+            "DTEND" if in_event => end = Some(parse_ics_date(value)?),
+            "SUMMARY" if in_event => name = Some(value.replace("\\,", ",")),
+            "END" if value == "VEVENT" && in_event => {
+                in_event = false;
+                let Some(first_day) = start.filter(|_| all_day) else {
+                    continue;
+                };
+                let last_day = end
+                    .map(|end| end - Duration::days(1))
+                    .filter(|last| *last >= first_day)
+                    .unwrap_or(first_day);
+
+                let mut day = first_day;
+                while day <= last_day {
+                    holidays.push(Holiday {
+                        date: day,
+                        name: name.clone(),
+                    });
+                    day += Duration::days(1);
+                }
+            }
+            _ => {}
+        }
+    }
+
+    Ok(holidays)
+}
+
+fn parse_ics_date(value: &str) -> Result<NaiveDate, AppError> {
+    // Either a DATE ("20251225") or a DATE-TIME ("20251225T000000Z"); only the day matters
+    let date = value.get(..8).unwrap_or(value);
+    NaiveDate::parse_from_str(date, "%Y%m%d")
+        .map_err(|_| AppError::Config(format!("Invalid date in calendar file: {}", value)))
+}
+
+/// Parses a Jira timestamp ("2025-01-06T10:00:00.000+0000") or an RFC 3339 one
+pub fn parse_jira_timestamp(value: &str) -> Option<DateTime<FixedOffset>> {
+    DateTime::parse_from_rfc3339(value)
+        .or_else(|_| DateTime::parse_from_str(value, "%Y-%m-%dT%H:%M:%S%.f%z"))
+        .ok()
+}
+
+/// Calendar hours between two instants, or 0 if `end` is before `start`
+pub fn hours_between(start: DateTime<FixedOffset>, end: DateTime<FixedOffset>) -> f64 {
+    ((end - start).num_seconds().max(0) as f64) / 3600.0
+}
 
 pub fn business_hours_between(
     start: NaiveDateTime,
     end: NaiveDateTime,
-    work_start_hour: u32,
-    work_end_hour: u32,
+    schedule: &WorkSchedule,
+    calendar: &WorkCalendar,
 ) -> Result<f64, AppError> {
-    // Validate work hours are in valid range (0-23)
-    if work_start_hour > 23 || work_end_hour > 23 {
-        return Err(AppError::Internal(format!(
-            "Invalid work hours: start={}, end={} (must be 0-23)",
-            work_start_hour, work_end_hour
-        )));
-    }
-
-    if work_start_hour >= work_end_hour {
-        return Err(AppError::Internal(format!(
-            "Work start hour ({}) must be less than work end hour ({})",
-            work_start_hour, work_end_hour
-        )));
-    }
+    schedule.validate()?;
 
     if end <= start {
         return Ok(0.0);
     }
 
-    let work_start = NaiveTime::from_hms_opt(work_start_hour, 0, 0)
-        .ok_or_else(|| AppError::Internal(format!("Invalid work start hour: {}", work_start_hour)))?;
-    let work_end = NaiveTime::from_hms_opt(work_end_hour, 0, 0)
-        .ok_or_else(|| AppError::Internal(format!("Invalid work end hour: {}", work_end_hour)))?;
+    if schedule.always_on {
+        return Ok((end - start).num_minutes() as f64 / 60.0);
+    }
+
     let mut total_minutes: i64 = 0;
 
     let mut current_date = start.date();
     let end_date = end.date();
 
     while current_date <= end_date {
-        let weekday = current_date.weekday();
-        if weekday != Weekday::Sat && weekday != Weekday::Sun {
-            let day_start = if current_date == start.date() {
-                start.time().max(work_start)
-            } else {
-                work_start
-            };
-            let day_end = if current_date == end_date {
-                end.time().min(work_end)
-            } else {
-                work_end
-            };
-
-            if day_end > day_start {
-                let diff = day_end - day_start;
-                total_minutes += diff.num_minutes();
+        if !calendar.is_holiday(current_date) {
+            for interval in schedule.intervals(current_date.weekday()) {
+                let (interval_start, interval_end) = interval.on(current_date);
+                let day_start = interval_start.max(start);
+                let day_end = interval_end.min(end);
+
+                if day_end > day_start {
+                    let diff = day_end - day_start;
+                    total_minutes += diff.num_minutes();
+                }
+            }
+        }
+        current_date += Duration::days(1);
+    }
+
+    Ok(total_minutes as f64 / 60.0)
+}
+
+/// Like `business_hours_between`, but for absolute instants. Each instant is converted into the
+/// team's timezone so working days follow the local calendar, including DST changes.
+pub fn business_hours_between_tz<T: TimeZone>(
+    start: DateTime<T>,
+    end: DateTime<T>,
+    team_tz: Tz,
+    schedule: &WorkSchedule,
+    calendar: &WorkCalendar,
+) -> Result<f64, AppError> {
+    schedule.validate()?;
+
+    let start = start.with_timezone(&team_tz);
+    let end = end.with_timezone(&team_tz);
+    if end <= start {
+        return Ok(0.0);
+    }
+
+    if schedule.always_on {
+        return Ok((end - start).num_minutes() as f64 / 60.0);
+    }
+
+    let mut total_minutes: i64 = 0;
+    let mut current_date = start.date_naive();
+    let end_date = end.date_naive();
+
+    while current_date <= end_date {
+        if !calendar.is_holiday(current_date) {
+            for interval in schedule.intervals(current_date.weekday()) {
+                // Intersect the working window with [start, end] as instants, so a day that
+                // gains or loses an hour is measured in real elapsed time
+                let (interval_start, interval_end) = interval.on(current_date);
+                let window_start = resolve_local(&team_tz, interval_start).max(start);
+                let window_end = resolve_local(&team_tz, interval_end).min(end);
+
+                if window_end > window_start {
+                    total_minutes += (window_end - window_start).num_minutes();
+                }
             }
         }
         current_date += Duration::days(1);
@@ -60,11 +425,45 @@ pub fn business_hours_between(
     Ok(total_minutes as f64 / 60.0)
 }
 
+/// Business hours between two Jira instants: in the team's timezone when one is configured,
+/// otherwise in the timestamps' own offsets
+pub fn business_hours_between_instants(
+    start: DateTime<FixedOffset>,
+    end: DateTime<FixedOffset>,
+    team_tz: Option<Tz>,
+    schedule: &WorkSchedule,
+    calendar: &WorkCalendar,
+) -> Result<f64, AppError> {
+    match team_tz {
+        Some(tz) => business_hours_between_tz(start, end, tz, schedule, calendar),
+        None => business_hours_between(start.naive_local(), end.naive_local(), schedule, calendar),
+    }
+}
+
+/// Maps a wall-clock time to an instant in `tz`. Ambiguous times (clocks going back) resolve to
+/// the earlier instant; times skipped by clocks going forward move to the first valid instant.
+pub(crate) fn resolve_local<T: TimeZone>(tz: &T, local: NaiveDateTime) -> DateTime<T> {
+    match tz.from_local_datetime(&local) {
+        LocalResult::Single(dt) => dt,
+        LocalResult::Ambiguous(earliest, _) => earliest,
+        LocalResult::None => (1..=8)
+            .find_map(|step| {
+                tz.from_local_datetime(&(local + Duration::minutes(15 * step)))
+                    .earliest()
+            })
+            .unwrap_or_else(|| tz.from_utc_datetime(&local)),
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use chrono::NaiveDate;
 
+    fn office_hours() -> WorkSchedule {
+        WorkSchedule::weekdays(9, 17).unwrap()
+    }
+
     #[test]
     fn test_business_hours_same_day() {
         let start = NaiveDate::from_ymd_opt(2025, 1, 6)
@@ -75,7 +474,8 @@ mod tests {
             .unwrap()
             .and_hms_opt(15, 0, 0)
             .unwrap();
-        let hours = business_hours_between(start, end, 9, 17).unwrap();
+        let hours =
+            business_hours_between(start, end, &office_hours(), &WorkCalendar::default()).unwrap();
         assert_eq!(hours, 5.0);
     }
 
@@ -90,7 +490,8 @@ mod tests {
             .unwrap()
             .and_hms_opt(10, 30, 0)
             .unwrap();
-        let hours = business_hours_between(start, end, 9, 17).unwrap();
+        let hours =
+            business_hours_between(start, end, &office_hours(), &WorkCalendar::default()).unwrap();
         // Monday: 16:00->17:00 = 1h
         // Tuesday: 09:00->17:00 = 8h
         // Wednesday: 09:00->10:30 = 1.5h
@@ -109,7 +510,8 @@ mod tests {
             .unwrap()
             .and_hms_opt(10, 0, 0)
             .unwrap();
-        let hours = business_hours_between(start, end, 9, 17).unwrap();
+        let hours =
+            business_hours_between(start, end, &office_hours(), &WorkCalendar::default()).unwrap();
         // Friday: 16:00->17:00 = 1h
         // Sat/Sun: 0h (excluded)
         // Monday: 09:00->10:00 = 1h
@@ -127,10 +529,219 @@ mod tests {
             .unwrap()
             .and_hms_opt(10, 0, 0)
             .unwrap();
-        let hours = business_hours_between(start, end, 9, 17).unwrap();
+        let hours =
+            business_hours_between(start, end, &office_hours(), &WorkCalendar::default()).unwrap();
+        assert_eq!(hours, 0.0);
+    }
+
+    #[test]
+    fn test_business_hours_tz_converts_offsets() {
+        // 15:00-23:00 in Berlin is 09:00-17:00 in New York
+        let start = parse_jira_timestamp("2025-01-06T15:00:00.000+0100").unwrap();
+        let end = parse_jira_timestamp("2025-01-06T23:00:00.000+0100").unwrap();
+        let tz = chrono_tz::America::New_York;
+        let hours =
+            business_hours_between_tz(start, end, tz, &office_hours(), &WorkCalendar::default())
+                .unwrap();
+        assert_eq!(hours, 8.0);
+    }
+
+    #[test]
+    fn test_business_hours_tz_across_dst_change() {
+        // Friday 16:00 CET to Monday 10:00 CEST; clocks went forward on Sunday 30 March
+        let start = parse_jira_timestamp("2025-03-28T16:00:00.000+0100").unwrap();
+        let end = parse_jira_timestamp("2025-03-31T10:00:00.000+0200").unwrap();
+        let tz = chrono_tz::Europe::Berlin;
+        let hours =
+            business_hours_between_tz(start, end, tz, &office_hours(), &WorkCalendar::default())
+                .unwrap();
+        assert_eq!(hours, 2.0);
+
+        // A Sunday night shift spans the switch itself: 00:00-06:00 lasts 5 hours in spring
+        // and 7 in autumn
+        let night_shift = WorkSchedule {
+            sunday: vec![WorkInterval {
+                start: NaiveTime::MIN,
+                end: NaiveTime::from_hms_opt(6, 0, 0).unwrap(),
+            }],
+            ..Default::default()
+        };
+        let weekend_hours = |start: &str, end: &str, schedule: &WorkSchedule| {
+            let start = parse_jira_timestamp(start).unwrap();
+            let end = parse_jira_timestamp(end).unwrap();
+            business_hours_between_tz(start, end, tz, schedule, &WorkCalendar::default()).unwrap()
+        };
+        let spring = ("2025-03-29T12:00:00.000+0100", "2025-03-31T12:00:00.000+0200");
+        let autumn = ("2025-10-25T12:00:00.000+0200", "2025-10-27T12:00:00.000+0100");
+        assert_eq!(weekend_hours(spring.0, spring.1, &night_shift), 5.0);
+        assert_eq!(weekend_hours(autumn.0, autumn.1, &night_shift), 7.0);
+
+        // Around the clock, the weekend has one hour less or more than 48
+        let always_on = WorkSchedule::always_on();
+        assert_eq!(weekend_hours(spring.0, spring.1, &always_on), 47.0);
+        assert_eq!(weekend_hours(autumn.0, autumn.1, &always_on), 49.0);
+    }
+
+    #[test]
+    fn test_business_hours_split_shift() {
+        // Monday 08:00 to 18:00 with 08:30-12:00 and 13:00-17:30
+        let schedule: WorkSchedule = serde_json::from_str(
+            r#"{"monday": [
+                {"start": "08:30:00", "end": "12:00:00"},
+                {"start": "13:00:00", "end": "17:30:00"}
+            ]}"#,
+        )
+        .unwrap();
+        let start = NaiveDate::from_ymd_opt(2025, 1, 6)
+            .unwrap()
+            .and_hms_opt(8, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 1, 6)
+            .unwrap()
+            .and_hms_opt(18, 0, 0)
+            .unwrap();
+        let hours =
+            business_hours_between(start, end, &schedule, &WorkCalendar::default()).unwrap();
+        assert_eq!(hours, 8.0);
+
+        // Tuesday has no intervals, so it isn't a working day
+        let next_day = end + Duration::days(1);
+        let hours =
+            business_hours_between(end, next_day, &schedule, &WorkCalendar::default()).unwrap();
         assert_eq!(hours, 0.0);
     }
 
+    #[test]
+    fn test_shift_ending_at_midnight() {
+        // Monday 22:00 to midnight, then Tuesday 00:00-06:00
+        let schedule: WorkSchedule = serde_json::from_str(
+            r#"{
+                "monday": [{"start": "22:00:00", "end": "00:00:00"}],
+                "tuesday": [{"start": "00:00:00", "end": "06:00:00"}]
+            }"#,
+        )
+        .unwrap();
+        let monday = NaiveDate::from_ymd_opt(2025, 1, 6).unwrap();
+        let start = monday.and_hms_opt(21, 0, 0).unwrap();
+        let end = monday.succ_opt().unwrap().and_hms_opt(0, 0, 0).unwrap();
+        let calendar = WorkCalendar::default();
+        assert_eq!(business_hours_between(start, end, &schedule, &calendar).unwrap(), 2.0);
+        let next_morning = end + Duration::hours(8);
+        assert_eq!(business_hours_between(start, next_morning, &schedule, &calendar).unwrap(), 8.0);
+
+        assert!(schedule.is_working_time(monday.and_hms_opt(23, 59, 59).unwrap()));
+        assert!(!schedule.is_working_time(monday.and_hms_opt(21, 59, 0).unwrap()));
+        let tz: Tz = "Europe/Berlin".parse().unwrap();
+        let (start, end) = (resolve_local(&tz, start), resolve_local(&tz, end));
+        assert_eq!(business_hours_between_tz(start, end, tz, &schedule, &calendar).unwrap(), 2.0);
+
+        // Nothing can follow an interval that runs to midnight
+        let mut overlapping = schedule.clone();
+        overlapping.monday.push(WorkInterval {
+            start: NaiveTime::from_hms_opt(23, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(23, 30, 0).unwrap(),
+        });
+        assert!(matches!(
+            overlapping.validate(),
+            Err(ScheduleError::OverlappingIntervals { .. })
+        ));
+    }
+
+    #[test]
+    fn test_always_on_priorities_count_weekends() {
+        let settings = BusinessHoursSettings {
+            always_on_priorities: vec!["Critical".to_string()],
+            ..Default::default()
+        };
+        // Friday 16:00 to Monday 10:00
+        let start = NaiveDate::from_ymd_opt(2025, 1, 10)
+            .unwrap()
+            .and_hms_opt(16, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2025, 1, 13)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        let calendar = WorkCalendar::default();
+
+        let critical = settings.schedule_for("Critical").unwrap();
+        assert_eq!(business_hours_between(start, end, &critical, &calendar).unwrap(), 66.0);
+
+        let low = settings.schedule_for("Low").unwrap();
+        assert_eq!(business_hours_between(start, end, &low, &calendar).unwrap(), 2.0);
+    }
+
+    #[test]
+    fn test_business_hours_holidays_excluded() {
+        // Tuesday 24 Dec 16:00 to Friday 27 Dec 10:00, with 25 and 26 Dec as holidays
+        let start = NaiveDate::from_ymd_opt(2024, 12, 24)
+            .unwrap()
+            .and_hms_opt(16, 0, 0)
+            .unwrap();
+        let end = NaiveDate::from_ymd_opt(2024, 12, 27)
+            .unwrap()
+            .and_hms_opt(10, 0, 0)
+            .unwrap();
+        let calendar = WorkCalendar::new(&[
+            Holiday {
+                date: NaiveDate::from_ymd_opt(2024, 12, 25).unwrap(),
+                name: Some("Christmas Day".to_string()),
+            },
+            Holiday {
+                date: NaiveDate::from_ymd_opt(2024, 12, 26).unwrap(),
+                name: None,
+            },
+        ]);
+        let hours = business_hours_between(start, end, &office_hours(), &calendar).unwrap();
+        // Tuesday: 1h, Wed/Thu: holidays, Friday: 1h
+        assert_eq!(hours, 2.0);
+    }
+
+    #[test]
+    fn test_parse_ics_holidays() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20251225\r\n\
+                   DTEND;VALUE=DATE:20251227\r\n\
+                   SUMMARY:Christmas\r\n  break\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART:20260101\r\n\
+                   SUMMARY:New Year\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let holidays = parse_ics_holidays(ics).unwrap();
+        let dates: Vec<String> = holidays.iter().map(|h| h.date.to_string()).collect();
+        assert_eq!(dates, vec!["2025-12-25", "2025-12-26", "2026-01-01"]);
+        assert_eq!(holidays[0].name.as_deref(), Some("Christmas break"));
+    }
+
+    #[test]
+    fn test_parse_ics_skips_timed_events() {
+        let ics = "BEGIN:VCALENDAR\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;TZID=Europe/Berlin:20251222T100000\r\n\
+                   DTEND;TZID=Europe/Berlin:20251222T110000\r\n\
+                   SUMMARY:Team meeting\r\n\
+                   END:VEVENT\r\n\
+                   BEGIN:VEVENT\r\n\
+                   DTSTART;VALUE=DATE:20251224\r\n\
+                   SUMMARY:Christmas Eve\r\n\
+                   END:VEVENT\r\n\
+                   END:VCALENDAR\r\n";
+        let holidays = parse_ics_holidays(ics).unwrap();
+        let dates: Vec<String> = holidays.iter().map(|h| h.date.to_string()).collect();
+        assert_eq!(dates, vec!["2025-12-24"]);
+    }
+
+    #[test]
+    fn test_parse_jira_timestamp_formats() {
+        let jira = parse_jira_timestamp("2025-01-06T10:00:00.000+0200").unwrap();
+        let rfc = parse_jira_timestamp("2025-01-06T08:00:00Z").unwrap();
+        assert_eq!(jira, rfc);
+        assert!(parse_jira_timestamp("not a date").is_none());
+    }
+
     #[test]
     fn test_invalid_work_hours() {
         let start = NaiveDate::from_ymd_opt(2025, 1, 6)
@@ -143,9 +754,23 @@ mod tests {
             .unwrap();
 
         // Test invalid hour > 23
-        assert!(business_hours_between(start, end, 25, 17).is_err());
+        assert!(matches!(
+            WorkSchedule::weekdays(25, 17),
+            Err(ScheduleError::InvalidHours { .. })
+        ));
 
         // Test start >= end
-        assert!(business_hours_between(start, end, 17, 9).is_err());
+        assert!(matches!(
+            WorkSchedule::weekdays(17, 9),
+            Err(ScheduleError::StartNotBeforeEnd { .. })
+        ));
+
+        // A deserialized schedule is validated before use
+        let mut schedule = office_hours();
+        schedule.monday.push(WorkInterval {
+            start: NaiveTime::from_hms_opt(16, 0, 0).unwrap(),
+            end: NaiveTime::from_hms_opt(18, 0, 0).unwrap(),
+        });
+        assert!(business_hours_between(start, end, &schedule, &WorkCalendar::default()).is_err());
     }
 }
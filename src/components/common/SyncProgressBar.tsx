@@ -16,7 +16,6 @@ export function SyncProgressBar() {
 
   const phaseLabels: Record<string, string> = {
     fetching: 'Fetching tickets from Jira...',
-    categorizing: 'Categorizing tickets...',
     saving: 'Saving to database...',
   };
 